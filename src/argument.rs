@@ -1,7 +1,9 @@
+use crate::message::{ArgumentKind, Messages};
+
 pub mod option;
 pub mod positional;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataType {
     Int32(bool),
     Float32(bool),
@@ -22,3 +24,37 @@ impl DataType {
         result.to_owned()
     }
 }
+
+// Set on arguments and sub-parsers that are being phased out. They are still
+// parsed, but using them adds a warning to the parse result.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Deprecation {
+    pub(crate) note: String,
+    pub(crate) replacement: Option<String>,
+}
+
+impl Deprecation {
+    pub(crate) fn new(note: &str, replacement: Option<&str>) -> Self {
+        Self {
+            note: note.to_string(),
+            replacement: replacement.map(|replacement| replacement.to_string()),
+        }
+    }
+
+    pub(crate) fn warning(
+        &self,
+        messages: &dyn Messages,
+        kind: ArgumentKind,
+        name: &str,
+    ) -> String {
+        messages.deprecated(kind, name, self.replacement.as_deref(), &self.note)
+    }
+}
+
+// Set on required arguments whose value is asked for when it's missing
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Prompt {
+    pub(crate) text: String,
+    // Secret answers aren't echoed, e.g. passwords
+    pub(crate) is_secret: bool,
+}
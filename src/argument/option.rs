@@ -1,135 +1,548 @@
+use std::ffi::{OsStr, OsString};
+
 use regex::Regex;
 
-use crate::{result::ParseValue, Parser};
+use crate::{
+    error::{DefinitionError, ValueReason},
+    result::ParseValue,
+    Parser,
+};
 
-use super::DataType;
+use super::{DataType, Deprecation, Prompt};
 
 #[cfg(test)]
 mod test;
 
+// What happens when a key of a key-value option is given more than once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    Reject,
+    LastWins,
+}
+
 #[derive(Debug, Clone)]
 pub struct OptionArgument {
-    pub(crate) short_name: Option<String>,
-    pub(crate) long_name: Option<String>,
+    pub(crate) short_names: Vec<String>,
+    pub(crate) long_names: Vec<String>,
+    pub(crate) hidden_names: Vec<String>,
     pub(crate) destination: String,
     pub(crate) data_type: DataType,
     pub(crate) is_required: Option<bool>,
     pub(crate) defaults: Option<Vec<String>>,
+    // The minimum and maximum number of values per occurrence
+    pub(crate) arity: Option<(usize, usize)>,
+    pub(crate) implicit_value: Option<String>,
+    pub(crate) value_delimiter: Option<char>,
+    pub(crate) key_values: Option<DuplicateKeys>,
+    pub(crate) help: Option<String>,
+    pub(crate) value_name: Option<String>,
+    pub(crate) is_hidden: bool,
+    pub(crate) deprecation: Option<Deprecation>,
+    // Whether values are stored in the destination of the replacement instead
+    pub(crate) is_forwarded: bool,
+    pub(crate) prompt: Option<Prompt>,
+    pub(crate) is_global: bool,
+    // The names and destinations used by the sub-parsers that already exist,
+    // which the option can't use if it's global. Sub-parsers added later
+    // inherit it right away, so their options check it themselves.
+    pub(crate) sub_parser_names: Vec<String>,
+    pub(crate) sub_parser_destinations: Vec<String>,
+    // Set on the copies of global options that sub-parsers inherit
+    pub(crate) is_inherited: bool,
 }
 
-// TODO: make non-array booleans have to have a default value
 impl OptionArgument {
     pub(crate) fn new(
         parser: &Parser,
         names: &str,
         destination: &str,
         data_type: DataType,
-    ) -> Self {
-        for positional in &parser.positionals {
-            if destination == positional.destination.as_str() {
-                panic!("destination '{}' is occupied by a positional", destination);
-            }
-        }
-        for option in &parser.options {
-            if destination == option.destination.as_str() {
-                panic!(
-                    "destination '{}' is occupied by another option",
-                    destination
-                );
-            }
+    ) -> Result<Self, DefinitionError> {
+        let is_occupied_destination = parser
+            .positionals
+            .iter()
+            .map(|positional| &positional.destination)
+            .chain(parser.options.iter().map(|option| &option.destination))
+            .any(|occupied_destination| occupied_destination == destination);
+        if is_occupied_destination {
+            return Err(DefinitionError::OccupiedDestination {
+                destination: destination.to_string(),
+            });
         }
 
-        let both_name_re = Regex::new(r"^-[A-Za-z0-9] *, *--[A-Za-z0-9-]+$").unwrap();
         let short_name_re = Regex::new(r"^-[A-Za-z0-9]$").unwrap();
         let long_name_re = Regex::new(r"^--[A-Za-z0-9-]+$").unwrap();
 
-        let (short_name, long_name) = if both_name_re.is_match(names) {
-            let stripped_names = names.replace(" ", "");
-            let (short_name, long_name) =
-                stripped_names.split_once(',').expect("checked with regex");
-            let short_name = short_name[1..].to_string();
-            let long_name = long_name[2..].to_string();
-            (Some(short_name), Some(long_name))
-        } else if short_name_re.is_match(names) {
-            let short_name = names[1..].to_string();
-            (Some(short_name), None)
-        } else if long_name_re.is_match(names) {
-            let long_name = names[2..].to_string();
-            (None, Some(long_name))
-        } else {
-            panic!("names of option '{}' need to be formatted like '-v', '--verbose', or '-v,--verbose'", destination);
-        };
+        let mut short_names: Vec<String> = Vec::new();
+        let mut long_names: Vec<String> = Vec::new();
+        for name in names.split(',').map(|name| name.trim()) {
+            let names = if short_name_re.is_match(name) {
+                &mut short_names
+            } else if long_name_re.is_match(name) {
+                &mut long_names
+            } else {
+                return Err(DefinitionError::InvalidOptionNames {
+                    destination: destination.to_string(),
+                });
+            };
 
-        for option in &parser.options {
-            if short_name.is_some() && option.short_name == short_name {
-                panic!(
-                    "short name '-{}' is occupied by another option",
-                    short_name.unwrap().as_str()
-                );
-            }
-            if long_name.is_some() && option.long_name == long_name {
-                panic!(
-                    "long name '--{}' is occupied by another option",
-                    long_name.unwrap().as_str()
-                );
+            let stripped_name = name.trim_start_matches('-').to_string();
+            if names.contains(&stripped_name) {
+                return Err(DefinitionError::RepeatedName {
+                    name: name.to_string(),
+                });
+            } else if parser.options.iter().any(|option| option.has_name(name))
+                || (parser.has_version_name(name) && !parser.is_version_inherited)
+            {
+                return Err(DefinitionError::OccupiedName {
+                    name: name.to_string(),
+                });
             }
+            names.push(stripped_name);
         }
 
-        Self {
-            short_name,
-            long_name,
+        let mut sub_parser_names = Vec::new();
+        let mut sub_parser_destinations = Vec::new();
+        parser.collect_sub_parser_occupants(&mut sub_parser_names, &mut sub_parser_destinations);
+
+        Ok(Self {
+            short_names,
+            long_names,
+            hidden_names: Vec::new(),
             destination: destination.to_string(),
             data_type,
             is_required: None,
             defaults: None,
-        }
+            arity: None,
+            implicit_value: None,
+            value_delimiter: None,
+            key_values: None,
+            help: None,
+            value_name: None,
+            is_hidden: false,
+            deprecation: None,
+            is_forwarded: false,
+            prompt: None,
+            is_global: false,
+            sub_parser_names,
+            sub_parser_destinations,
+            is_inherited: false,
+        })
     }
 
     pub(crate) fn has_name(&self, name: &str) -> bool {
-        if name.starts_with("--") {
-            let name = name[2..].to_string();
-            self.long_name == Some(name)
-        } else if name.starts_with('-') {
-            let name = name[1..].to_string();
-            self.short_name == Some(name)
+        if let Some(name) = name.strip_prefix("--") {
+            self.long_names.iter().any(|long_name| long_name == name)
+        } else if let Some(name) = name.strip_prefix('-') {
+            self.short_names.iter().any(|short_name| short_name == name)
         } else {
             false
         }
     }
 
+    // Only long names can be abbreviated, since short names are a single character.
+    // The names that are shown to users, in help output as well as in errors
+    pub(crate) fn visible_names(&self) -> Vec<String> {
+        if self.is_hidden {
+            return Vec::new();
+        }
+        self.short_names
+            .iter()
+            .map(|short_name| format!("-{}", short_name))
+            .chain(
+                self.long_names
+                    .iter()
+                    .map(|long_name| format!("--{}", long_name)),
+            )
+            .filter(|name| !self.hidden_names.contains(name))
+            .collect()
+    }
+
+    // The visible long name that an abbreviation like '--verb' stands for
+    pub(crate) fn find_name_with_prefix(&self, name: &str) -> Option<String> {
+        match name.strip_prefix("--") {
+            Some(prefix) if !prefix.is_empty() => self
+                .visible_names()
+                .into_iter()
+                .find(|visible_name| visible_name.starts_with(&format!("--{}", prefix))),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn display_name(&self) -> String {
+        match (self.short_names.first(), self.long_names.first()) {
+            (_, Some(long_name)) => format!("--{}", long_name),
+            (Some(short_name), None) => format!("-{}", short_name),
+            (None, None) => unreachable!("options always have at least one name"),
+        }
+    }
+
+    // Hidden names are still parsed, but are left out of help output, errors
+    // and abbreviations.
+    pub fn hidden_names(&mut self, names: &[&str]) -> &mut Self {
+        self.try_hidden_names(names)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_hidden_names(&mut self, names: &[&str]) -> Result<&mut Self, DefinitionError> {
+        let mut hidden_names = self.hidden_names.clone();
+        for name in names {
+            if !self.has_name(name) {
+                return Err(DefinitionError::UnknownHiddenName {
+                    destination: self.destination.clone(),
+                    name: name.to_string(),
+                });
+            }
+            if !hidden_names.iter().any(|hidden_name| hidden_name == name) {
+                hidden_names.push(name.to_string());
+            }
+        }
+        if hidden_names.len() == self.short_names.len() + self.long_names.len() {
+            return Err(DefinitionError::AllNamesHidden {
+                destination: self.destination.clone(),
+            });
+        }
+        self.hidden_names = hidden_names;
+        Ok(self)
+    }
+
+    pub fn help(&mut self, help: &str) -> &mut Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    // The name of the option's values in help output, which defaults
+    // to the destination in upper case, e.g. '--output <FILE>'.
+    pub fn value_name(&mut self, value_name: &str) -> &mut Self {
+        self.value_name = Some(value_name.to_string());
+        self
+    }
+
+    // Hidden options are still parsed by their full names, but are left out of
+    // help output, errors and abbreviations.
+    pub fn hidden(&mut self, is_hidden: bool) -> &mut Self {
+        self.is_hidden = is_hidden;
+        self
+    }
+
+    // Using a deprecated option adds a warning to the parse result, which points
+    // at the replacement if there is one. The replacement is the name of another
+    // option of the same parser, e.g. '--color'.
+    pub fn deprecated(&mut self, note: &str, replacement: Option<&str>) -> &mut Self {
+        self.deprecation = Some(Deprecation::new(note, replacement));
+        self
+    }
+
+    // Makes a deprecated option act as its replacement, so that its values
+    // end up in the destination of the replacement.
+    pub fn forward_to_replacement(&mut self, is_forwarded: bool) -> &mut Self {
+        self.is_forwarded = is_forwarded;
+        self
+    }
+
+    // A missing required option is asked for with this prompt instead of
+    // causing an error, if the parser's prompter is interactive.
+    pub fn prompt(&mut self, text: &str) -> &mut Self {
+        self.prompt = Some(Prompt {
+            text: text.to_string(),
+            is_secret: false,
+        });
+        self
+    }
+
+    // Like prompt, but the answer isn't shown while it's typed
+    pub fn secret_prompt(&mut self, text: &str) -> &mut Self {
+        self.prompt = Some(Prompt {
+            text: text.to_string(),
+            is_secret: true,
+        });
+        self
+    }
+
+    // Global options are inherited by all sub-parsers, but their values
+    // always end up in the result of the parser that defined them.
+    pub fn global(&mut self, is_global: bool) -> &mut Self {
+        self.try_global(is_global)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_global(&mut self, is_global: bool) -> Result<&mut Self, DefinitionError> {
+        if is_global {
+            if self.sub_parser_destinations.contains(&self.destination) {
+                return Err(DefinitionError::OccupiedDestination {
+                    destination: self.destination.clone(),
+                });
+            }
+            if let Some(name) = self
+                .sub_parser_names
+                .iter()
+                .find(|name| self.has_name(name))
+            {
+                return Err(DefinitionError::OccupiedName { name: name.clone() });
+            }
+        }
+        self.is_global = is_global;
+        Ok(self)
+    }
+
     pub fn is_required(&mut self, is_required: bool) -> &mut Self {
+        self.try_is_required(is_required)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_is_required(&mut self, is_required: bool) -> Result<&mut Self, DefinitionError> {
         if is_required && self.defaults.is_some() {
-            panic!(
-                "option '{}' cannot be required and have a default value simultaneously",
-                &self.destination
-            );
+            return Err(DefinitionError::RequiredWithDefault {
+                destination: self.destination.clone(),
+            });
         }
         self.is_required = Some(is_required);
-        self
+        Ok(self)
+    }
+
+    // Makes every occurrence of the option take exactly 'num_values' values,
+    // which are stored as a group in the parse result.
+    pub fn arity(&mut self, num_values: usize) -> &mut Self {
+        self.try_arity_range(num_values, num_values)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_arity(&mut self, num_values: usize) -> Result<&mut Self, DefinitionError> {
+        self.try_arity_range(num_values, num_values)
+    }
+
+    // Like arity, but every occurrence takes 'min_values' to 'max_values' values.
+    // Values beyond the minimum are only taken until something looks like an option.
+    pub fn arity_range(&mut self, min_values: usize, max_values: usize) -> &mut Self {
+        self.try_arity_range(min_values, max_values)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_arity_range(
+        &mut self,
+        min_values: usize,
+        max_values: usize,
+    ) -> Result<&mut Self, DefinitionError> {
+        let is_flag = matches!(self.data_type, DataType::Bool(false));
+        if is_flag || self.implicit_value.is_some() || max_values == 0 || min_values > max_values {
+            return Err(DefinitionError::InvalidArity {
+                destination: self.destination.clone(),
+            });
+        }
+        if self.value_delimiter.is_some() {
+            return Err(DefinitionError::InvalidValueDelimiter {
+                destination: self.destination.clone(),
+            });
+        }
+        if self.key_values.is_some() {
+            return Err(DefinitionError::InvalidKeyValues {
+                destination: self.destination.clone(),
+            });
+        }
+        if let Some(defaults) = &self.defaults {
+            if defaults.len() < min_values || defaults.len() > max_values {
+                return Err(DefinitionError::DefaultsOutsideArity {
+                    destination: self.destination.clone(),
+                });
+            }
+        }
+        self.arity = Some((min_values, max_values));
+        Ok(self)
+    }
+
+    // Makes the value of the option optional, e.g. '--color[=WHEN]'. The implicit
+    // value is used when the option is given without '=value', while the
+    // defaults are still used when the option isn't given at all.
+    pub fn implicit_value(&mut self, implicit_value: &str) -> &mut Self {
+        self.try_implicit_value(implicit_value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_implicit_value(
+        &mut self,
+        implicit_value: &str,
+    ) -> Result<&mut Self, DefinitionError> {
+        let is_flag = matches!(self.data_type, DataType::Bool(false));
+        if is_flag || self.arity.is_some() {
+            return Err(DefinitionError::UnexpectedImplicitValue {
+                destination: self.destination.clone(),
+            });
+        }
+        if let Err(reason) = self.validate_value(implicit_value) {
+            return Err(DefinitionError::InvalidImplicitValue {
+                destination: self.destination.clone(),
+                value: implicit_value.to_string(),
+                reason,
+            });
+        }
+        self.implicit_value = Some(implicit_value.to_string());
+        Ok(self)
+    }
+
+    // Splits every value of an array option on the delimiter, e.g. '--tag a,b,c'
+    // with ','. A delimiter preceded by a backslash is kept as is, as is
+    // a backslash preceded by another backslash.
+    pub fn value_delimiter(&mut self, delimiter: char) -> &mut Self {
+        self.try_value_delimiter(delimiter)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_value_delimiter(&mut self, delimiter: char) -> Result<&mut Self, DefinitionError> {
+        // Only ASCII delimiters can be split on without decoding the values
+        if !self.data_type.is_array()
+            || self.arity.is_some()
+            || !delimiter.is_ascii()
+            || delimiter == '\\'
+        {
+            return Err(DefinitionError::InvalidValueDelimiter {
+                destination: self.destination.clone(),
+            });
+        }
+        self.value_delimiter = Some(delimiter);
+        Ok(self)
+    }
+
+    pub(crate) fn split_value(&self, value: &OsStr) -> Vec<OsString> {
+        let delimiter = match self.value_delimiter {
+            Some(delimiter) => delimiter as u8,
+            None => return vec![value.to_os_string()],
+        };
+
+        let mut values = Vec::new();
+        let mut current_value = Vec::new();
+        let mut bytes = value.as_encoded_bytes().iter().peekable();
+        while let Some(byte) = bytes.next() {
+            if *byte == b'\\' {
+                match bytes.next_if(|next| **next == delimiter || **next == b'\\') {
+                    Some(escaped) => current_value.push(*escaped),
+                    None => current_value.push(*byte),
+                }
+            } else if *byte == delimiter {
+                values.push(std::mem::take(&mut current_value));
+            } else {
+                current_value.push(*byte);
+            }
+        }
+        values.push(current_value);
+
+        values
+            .into_iter()
+            // Only ASCII bytes were removed, so the rest of the encoded bytes stay valid
+            .map(|value| unsafe { OsString::from_encoded_bytes_unchecked(value) })
+            .collect()
+    }
+
+    // Makes every value of an array option a 'KEY=VALUE' pair, e.g. '-D name=value',
+    // where the value is of the option's data type. Defaults and the implicit
+    // value need to be pairs as well.
+    pub fn key_values(&mut self, duplicate_keys: Option<DuplicateKeys>) -> &mut Self {
+        self.try_key_values(duplicate_keys)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_key_values(
+        &mut self,
+        duplicate_keys: Option<DuplicateKeys>,
+    ) -> Result<&mut Self, DefinitionError> {
+        if duplicate_keys.is_some() && (!self.data_type.is_array() || self.arity.is_some()) {
+            return Err(DefinitionError::InvalidKeyValues {
+                destination: self.destination.clone(),
+            });
+        }
+
+        let previous_key_values = self.key_values;
+        self.key_values = duplicate_keys;
+        if let Some(implicit_value) = &self.implicit_value {
+            if let Err(reason) = self.validate_value(implicit_value) {
+                let err = DefinitionError::InvalidImplicitValue {
+                    destination: self.destination.clone(),
+                    value: implicit_value.clone(),
+                    reason,
+                };
+                self.key_values = previous_key_values;
+                return Err(err);
+            }
+        }
+        for default_value in self.defaults.iter().flatten() {
+            if let Err(reason) = self.validate_value(default_value) {
+                let err = DefinitionError::InvalidDefault {
+                    destination: self.destination.clone(),
+                    value: default_value.clone(),
+                    reason,
+                };
+                self.key_values = previous_key_values;
+                return Err(err);
+            }
+        }
+        if let Some(defaults) = &self.defaults {
+            if let Err(err) = self.check_default_keys(defaults) {
+                self.key_values = previous_key_values;
+                return Err(err);
+            }
+        }
+        Ok(self)
+    }
+
+    // With DuplicateKeys::Reject, the defaults can't repeat a key either
+    fn check_default_keys(&self, defaults: &[impl AsRef<str>]) -> Result<(), DefinitionError> {
+        if self.key_values != Some(DuplicateKeys::Reject) {
+            return Ok(());
+        }
+        let mut keys = Vec::new();
+        for default_value in defaults {
+            let (key, _) = ParseValue::from_pair(self.data_type, default_value.as_ref())
+                .expect("defaults are validated before their keys");
+            if keys.contains(&key) {
+                return Err(DefinitionError::DuplicateDefaultKey {
+                    destination: self.destination.clone(),
+                    key,
+                });
+            }
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    fn validate_value(&self, value: &str) -> Result<(), ValueReason> {
+        match self.key_values {
+            Some(_) => ParseValue::from_pair(self.data_type, value).map(|_| ()),
+            None => ParseValue::from_value(self.data_type, value).map(|_| ()),
+        }
     }
 
     pub fn defaults(&mut self, defaults: &[&str]) -> &mut Self {
-        if !self.data_type.is_array() && defaults.len() > 1 {
-            panic!(
-                "option '{}' is not an array and can only have one default value",
-                &self.destination
-            );
+        self.try_defaults(defaults)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_defaults(&mut self, defaults: &[&str]) -> Result<&mut Self, DefinitionError> {
+        if let Some((min_values, max_values)) = self.arity {
+            if defaults.len() < min_values || defaults.len() > max_values {
+                return Err(DefinitionError::DefaultsOutsideArity {
+                    destination: self.destination.clone(),
+                });
+            }
+        } else if !self.data_type.is_array() && defaults.len() > 1 {
+            return Err(DefinitionError::TooManyDefaults {
+                destination: self.destination.clone(),
+            });
         }
         if self.is_required == Some(true) {
-            panic!(
-                "option '{}' cannot be required and have a default value simultaneously",
-                &self.destination
-            );
+            return Err(DefinitionError::RequiredWithDefault {
+                destination: self.destination.clone(),
+            });
         }
         for default_value in defaults {
-            if let Err(err) = ParseValue::from_value(self.data_type, default_value) {
-                panic!(
-                    "option '{}' default value is invalid: {}",
-                    &self.destination, &err
-                );
+            if let Err(reason) = self.validate_value(default_value) {
+                return Err(DefinitionError::InvalidDefault {
+                    destination: self.destination.clone(),
+                    value: default_value.to_string(),
+                    reason,
+                });
             }
         }
+        self.check_default_keys(defaults)?;
         self.defaults = Some(defaults.iter().map(|default| default.to_string()).collect());
-        self
+        Ok(self)
     }
 }
@@ -1,4 +1,8 @@
-use crate::{argument::DataType, Parser};
+use crate::{
+    argument::{option::DuplicateKeys, DataType},
+    error::DefinitionError,
+    Parser,
+};
 
 struct Input<'a> {
     names: &'a str,
@@ -186,6 +190,120 @@ mod valid {
         assert_eq!(parser.options.len(), inputs.len());
     }
 
+    #[test]
+    fn many_aliases() {
+        let inputs = [
+            Input {
+                names: "-o, --out, --output",
+                destination: "a",
+                data_type: DataType::Path(false),
+                is_required: None,
+                defaults: None,
+            },
+            Input {
+                names: "-q,-s,--quiet,--silent",
+                destination: "b",
+                data_type: DataType::Bool(false),
+                is_required: None,
+                defaults: Some(&["false"]),
+            },
+        ];
+
+        let result = catch_unwind(|| create_parser_with_inputs(&inputs));
+        assert!(result.is_ok());
+        let parser = result.expect("just checked that it's Ok(_)");
+        assert_eq!(parser.options[0].short_names, ["o"]);
+        assert_eq!(parser.options[0].long_names, ["out", "output"]);
+        assert_eq!(parser.options[1].short_names, ["q", "s"]);
+        assert_eq!(parser.options[1].long_names, ["quiet", "silent"]);
+    }
+
+    #[test]
+    fn hidden_alias() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser
+                .option("-o, --output, --out", "output", DataType::Path(false))
+                .hidden_names(&["--out"]);
+            parser
+        });
+        assert!(result.is_ok());
+        let parser = result.expect("just checked that it's Ok(_)");
+        assert_eq!(parser.options[0].hidden_names, ["--out"]);
+    }
+
+    #[test]
+    fn invalid_value_delimiter() {
+        let mut parser = Parser::new();
+        let option = parser.option("--name", "name", DataType::String(false));
+        assert_eq!(
+            option.try_value_delimiter(',').unwrap_err(),
+            DefinitionError::InvalidValueDelimiter {
+                destination: "name".to_string()
+            }
+        );
+
+        let option = parser.option("--tag", "tags", DataType::String(true));
+        assert!(option.try_value_delimiter('\\').is_err());
+        assert!(option.try_value_delimiter('ä').is_err());
+        option.try_value_delimiter(',').unwrap();
+        assert!(option.try_arity(2).is_err());
+    }
+
+    #[test]
+    fn invalid_key_values() {
+        let mut parser = Parser::new();
+        let option = parser.option("-D", "define", DataType::String(false));
+        assert_eq!(
+            option
+                .try_key_values(Some(DuplicateKeys::Reject))
+                .unwrap_err(),
+            DefinitionError::InvalidKeyValues {
+                destination: "define".to_string()
+            }
+        );
+
+        let option = parser.option("--level", "levels", DataType::Int32(true));
+        option.defaults(&["1"]);
+        assert!(matches!(
+            option.try_key_values(Some(DuplicateKeys::LastWins)),
+            Err(DefinitionError::InvalidDefault { .. })
+        ));
+
+        let option = parser.option("--limit", "limits", DataType::Int32(true));
+        option.key_values(Some(DuplicateKeys::LastWins));
+        option.defaults(&["a=1", "b=2"]);
+        assert!(matches!(
+            option.try_defaults(&["a"]),
+            Err(DefinitionError::InvalidDefault { .. })
+        ));
+        assert!(matches!(
+            option.try_defaults(&["=1"]),
+            Err(DefinitionError::InvalidDefault { .. })
+        ));
+        assert!(option.try_arity(2).is_err());
+        assert!(option.try_key_values(None).is_err());
+
+        // Only duplicate keys in the defaults of options that reject them
+        option.defaults(&["a=1", "a=2"]);
+        assert_eq!(
+            option
+                .try_key_values(Some(DuplicateKeys::Reject))
+                .unwrap_err(),
+            DefinitionError::DuplicateDefaultKey {
+                destination: "limits".to_string(),
+                key: "a".to_string()
+            }
+        );
+        assert_eq!(option.key_values, Some(DuplicateKeys::LastWins));
+        let option = parser.option("--quota", "quotas", DataType::Int32(true));
+        option.key_values(Some(DuplicateKeys::Reject));
+        assert!(matches!(
+            option.try_defaults(&["a=1", "b=2", "a=3"]),
+            Err(DefinitionError::DuplicateDefaultKey { .. })
+        ));
+    }
+
     #[ignore = "parsing has not been implemented yet, so can't be tested"]
     #[test]
     fn correct_default_types() {
@@ -337,6 +455,166 @@ mod invalid {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn duplicate_alias() {
+        let inputs = [
+            Input {
+                names: "-o, --out, --output",
+                destination: "a_destination",
+                data_type: DataType::Path(false),
+                is_required: None,
+                defaults: None,
+            },
+            Input {
+                names: "--output-file, --output",
+                destination: "another_destination",
+                data_type: DataType::Path(false),
+                is_required: None,
+                defaults: None,
+            },
+        ];
+
+        let result = catch_unwind(|| create_parser_with_inputs(&inputs));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn repeated_own_alias() {
+        let inputs = [Input {
+            names: "--out, -o, --out",
+            destination: "a_destination",
+            data_type: DataType::Path(false),
+            is_required: None,
+            defaults: None,
+        }];
+
+        let result = catch_unwind(|| create_parser_with_inputs(&inputs));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn hidden_unknown_name() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser
+                .option("-o, --output", "output", DataType::Path(false))
+                .hidden_names(&["--out"]);
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn hidden_all_names() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser
+                .option("-o, --output", "output", DataType::Path(false))
+                .hidden_names(&["-o", "--output"]);
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn fallible_definitions() {
+        let mut parser = Parser::new();
+        parser
+            .try_option("-a", "a_destination", DataType::Int32(false))
+            .unwrap();
+        assert_eq!(
+            parser
+                .try_option("-b", "a_destination", DataType::Int32(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedDestination {
+                destination: "a_destination".to_string()
+            }
+        );
+        assert_eq!(
+            parser
+                .try_option("-c, -a", "another_destination", DataType::Int32(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedName {
+                name: "-a".to_string()
+            }
+        );
+        assert_eq!(
+            parser
+                .try_option(
+                    "-n --no-comma",
+                    "another_destination",
+                    DataType::Int32(false)
+                )
+                .unwrap_err(),
+            DefinitionError::InvalidOptionNames {
+                destination: "another_destination".to_string()
+            }
+        );
+
+        let option = parser
+            .try_option("--number", "number", DataType::Int32(false))
+            .unwrap();
+        assert!(matches!(
+            option.try_defaults(&["three"]),
+            Err(DefinitionError::InvalidDefault { .. })
+        ));
+        option.try_defaults(&["3"]).unwrap();
+        assert_eq!(
+            option.try_is_required(true).unwrap_err(),
+            DefinitionError::RequiredWithDefault {
+                destination: "number".to_string()
+            }
+        );
+        assert_eq!(parser.options.len(), 2);
+    }
+
+    #[test]
+    fn invalid_arity() {
+        let mut parser = Parser::new();
+        let option = parser.option("--size", "size", DataType::Int32(false));
+        assert_eq!(
+            option.try_arity(0).unwrap_err(),
+            DefinitionError::InvalidArity {
+                destination: "size".to_string()
+            }
+        );
+        assert!(option.try_arity_range(3, 2).is_err());
+        option.try_arity(2).unwrap();
+        assert_eq!(
+            option.try_defaults(&["800"]).unwrap_err(),
+            DefinitionError::DefaultsOutsideArity {
+                destination: "size".to_string()
+            }
+        );
+        option.try_defaults(&["800", "600"]).unwrap();
+        assert!(option.try_arity_range(3, 4).is_err());
+
+        let flag = parser.option("--flag", "flag", DataType::Bool(false));
+        assert!(flag.try_arity(1).is_err());
+    }
+
+    #[test]
+    fn invalid_implicit_value() {
+        let mut parser = Parser::new();
+        let option = parser.option("--level", "level", DataType::Int32(false));
+        assert!(matches!(
+            option.try_implicit_value("high"),
+            Err(DefinitionError::InvalidImplicitValue { .. })
+        ));
+        option.try_implicit_value("3").unwrap();
+        assert!(option.try_arity(2).is_err());
+
+        let sized = parser.option("--size", "size", DataType::Int32(false));
+        sized.arity(2);
+        assert_eq!(
+            sized.try_implicit_value("1").unwrap_err(),
+            DefinitionError::UnexpectedImplicitValue {
+                destination: "size".to_string()
+            }
+        );
+
+        let flag = parser.option("--flag", "flag", DataType::Bool(false));
+        assert!(flag.try_implicit_value("true").is_err());
+    }
+
     #[ignore = "parsing has not been implemented yet, so can't be tested"]
     #[test]
     fn wrong_default_types() {
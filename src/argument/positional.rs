@@ -1,103 +1,198 @@
-use crate::{result::ParseValue, Parser};
+use crate::{error::DefinitionError, result::ParseValue, Parser};
 
-use super::DataType;
+use super::{DataType, Deprecation, Prompt};
 
 #[cfg(test)]
 mod test;
 
 #[derive(Debug, Clone)]
 pub struct PositionalArgument {
-    pub(crate) parser: *const Parser,
+    // Sub-parsers added after this positional check it themselves,
+    // so only the ones added before it need to be known here.
+    pub(crate) has_sub_parsers: bool,
+    // Positionals are filled from the front, so the ones after an optional
+    // positional are optional too. The ones after an array get the last
+    // values, so they can't be left out.
+    pub(crate) is_after_optional: bool,
+    pub(crate) is_after_array: bool,
     pub(crate) destination: String,
     pub(crate) data_type: DataType,
     pub(crate) is_required: Option<bool>,
     pub(crate) defaults: Option<Vec<String>>,
+    pub(crate) help: Option<String>,
+    pub(crate) is_hidden: bool,
+    pub(crate) deprecation: Option<Deprecation>,
+    pub(crate) prompt: Option<Prompt>,
 }
 
 impl PositionalArgument {
-    pub(crate) fn new(parser: &Parser, destination: &str, data_type: DataType) -> Self {
-        for positional in &parser.positionals {
-            if destination == positional.destination.as_str() {
-                panic!(
-                    "destination '{}' is occupied by another positional",
-                    destination
-                );
-            }
-        }
-        for option in &parser.options {
-            if destination == option.destination.as_str() {
-                panic!("destination '{}' is occupied by an option", destination);
-            }
+    pub(crate) fn new(
+        parser: &Parser,
+        destination: &str,
+        data_type: DataType,
+    ) -> Result<Self, DefinitionError> {
+        let is_occupied_destination = parser
+            .positionals
+            .iter()
+            .map(|positional| &positional.destination)
+            .chain(parser.options.iter().map(|option| &option.destination))
+            .any(|occupied_destination| occupied_destination == destination);
+        if is_occupied_destination {
+            return Err(DefinitionError::OccupiedDestination {
+                destination: destination.to_string(),
+            });
         }
 
         if data_type.is_array() && !parser.child_parsers.is_empty() {
-            panic!(
-                "positional array '{}' cannot be added, since a sub-parser has been added",
-                &destination
-            );
+            return Err(DefinitionError::ArrayPositionalWithSubParser {
+                destination: destination.to_string(),
+            });
         }
 
-        if let Some(prev_positional) = parser.positionals.back() {
-            let prev_is_array = prev_positional.data_type.is_array();
-            let prev_is_optional = prev_positional.is_required == Some(false)
-                || (prev_positional.is_required == None && prev_positional.defaults.is_some());
+        // The positionals after an array get the last values, e.g. 'cp SRC... DEST'
+        let is_after_array = parser
+            .positionals
+            .iter()
+            .any(|positional| positional.data_type.is_array());
+        if data_type.is_array() && is_after_array {
+            return Err(DefinitionError::MultipleArrayPositionals {
+                destination: destination.to_string(),
+            });
+        }
 
-            if prev_is_optional {
-                panic!("only the last positional can be optional");
-            } else if prev_is_array {
-                panic!("only the last positional can be an array");
-            }
+        // It could be neither required nor optional, e.g. '[A] ARRAY... B'
+        let is_after_optional = parser
+            .positionals
+            .iter()
+            .any(|positional| positional.is_optional());
+        if is_after_array && is_after_optional {
+            return Err(DefinitionError::PositionalAfterOptionalArray {
+                destination: destination.to_string(),
+            });
         }
 
-        Self {
-            parser: parser as *const Parser,
+        Ok(Self {
+            has_sub_parsers: !parser.child_parsers.is_empty(),
+            is_after_optional,
+            is_after_array,
             destination: destination.to_string(),
             data_type,
             is_required: None,
             defaults: None,
-        }
+            help: None,
+            is_hidden: false,
+            deprecation: None,
+            prompt: None,
+        })
+    }
+
+    pub(crate) fn is_optional(&self) -> bool {
+        self.is_required == Some(false) || self.defaults.is_some()
+    }
+
+    pub fn help(&mut self, help: &str) -> &mut Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    // Hidden positionals are still parsed, but are left out of help output.
+    pub fn hidden(&mut self, is_hidden: bool) -> &mut Self {
+        self.is_hidden = is_hidden;
+        self
+    }
+
+    // Giving a deprecated positional a value adds a warning to the parse result,
+    // which points at the replacement if there is one.
+    pub fn deprecated(&mut self, note: &str, replacement: Option<&str>) -> &mut Self {
+        self.deprecation = Some(Deprecation::new(note, replacement));
+        self
+    }
+
+    // A missing required positional is asked for with this prompt instead of
+    // causing an error, if the parser's prompter is interactive.
+    pub fn prompt(&mut self, text: &str) -> &mut Self {
+        self.prompt = Some(Prompt {
+            text: text.to_string(),
+            is_secret: false,
+        });
+        self
+    }
+
+    // Like prompt, but the answer isn't shown while it's typed
+    pub fn secret_prompt(&mut self, text: &str) -> &mut Self {
+        self.prompt = Some(Prompt {
+            text: text.to_string(),
+            is_secret: true,
+        });
+        self
     }
 
     pub fn is_required(&mut self, is_required: bool) -> &mut Self {
-        let parser = unsafe { self.parser.as_ref().expect("should not be null") };
-        if !is_required && !parser.child_parsers.is_empty() {
-            panic!(
-                "optional positional '{}' cannot be added, since a sub-parser has been added",
-                &self.destination
-            );
+        self.try_is_required(is_required)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_is_required(&mut self, is_required: bool) -> Result<&mut Self, DefinitionError> {
+        if !is_required && self.has_sub_parsers {
+            return Err(DefinitionError::OptionalPositionalWithSubParser {
+                destination: self.destination.clone(),
+            });
+        }
+        if !is_required && self.is_after_array {
+            return Err(DefinitionError::OptionalPositionalAfterArray {
+                destination: self.destination.clone(),
+            });
+        }
+        if is_required && self.is_after_optional {
+            return Err(DefinitionError::RequiredPositionalAfterOptional {
+                destination: self.destination.clone(),
+            });
         }
         if is_required && self.defaults.is_some() {
-            panic!(
-                "positional '{}' cannot be required and have a default value simultaneously",
-                &self.destination
-            );
+            return Err(DefinitionError::RequiredWithDefault {
+                destination: self.destination.clone(),
+            });
         }
         self.is_required = Some(is_required);
-        self
+        Ok(self)
     }
 
     pub fn defaults(&mut self, defaults: &[&str]) -> &mut Self {
+        self.try_defaults(defaults)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_defaults(&mut self, defaults: &[&str]) -> Result<&mut Self, DefinitionError> {
+        if self.has_sub_parsers {
+            return Err(DefinitionError::OptionalPositionalWithSubParser {
+                destination: self.destination.clone(),
+            });
+        }
+        if self.is_after_array {
+            return Err(DefinitionError::OptionalPositionalAfterArray {
+                destination: self.destination.clone(),
+            });
+        }
         if !self.data_type.is_array() && defaults.len() > 1 {
-            panic!(
-                "positional '{}' is not an array and can only have one default value",
-                &self.destination
-            );
+            return Err(DefinitionError::TooManyDefaults {
+                destination: self.destination.clone(),
+            });
         }
         if self.is_required == Some(true) {
-            panic!(
-                "positional '{}' cannot be required and have a default value simultaneously",
-                &self.destination
-            );
+            return Err(DefinitionError::RequiredWithDefault {
+                destination: self.destination.clone(),
+            });
         }
         for default_value in defaults {
-            if let Err(err) = ParseValue::from_value(self.data_type, default_value) {
-                panic!(
-                    "positional '{}' default value is invalid: {}",
-                    &self.destination, &err
-                );
+            if let Err(reason) = ParseValue::from_value(self.data_type, default_value) {
+                return Err(DefinitionError::InvalidDefault {
+                    destination: self.destination.clone(),
+                    value: default_value.to_string(),
+                    reason,
+                });
             }
         }
         self.defaults = Some(defaults.iter().map(|default| default.to_string()).collect());
-        self
+        Ok(self)
     }
 }
@@ -1,6 +1,6 @@
 use std::panic::catch_unwind;
 
-use crate::{argument::DataType, Parser};
+use crate::{argument::DataType, error::DefinitionError, Parser};
 
 struct Input<'a> {
     destination: &'a str,
@@ -177,6 +177,64 @@ mod valid {
         assert_eq!(parser.positionals.len(), inputs.len());
     }
 
+    #[test]
+    fn optional_tail() {
+        let inputs: [Input; 4] = [
+            Input {
+                destination: "pos_1",
+                data_type: DataType::Path(false),
+                is_required: Some(true),
+                defaults: None,
+            },
+            Input {
+                destination: "pos_2",
+                data_type: DataType::Path(false),
+                is_required: Some(false),
+                defaults: None,
+            },
+            Input {
+                destination: "pos_3",
+                data_type: DataType::Path(false),
+                is_required: None,
+                defaults: Some(&["."]),
+            },
+            Input {
+                destination: "pos_4",
+                data_type: DataType::Path(true),
+                is_required: None,
+                defaults: None,
+            },
+        ];
+
+        let result = catch_unwind(|| create_parser_with_inputs(&inputs));
+        assert!(result.is_ok());
+        let parser = result.expect("just checked that it's Ok(_)");
+        assert_eq!(parser.positionals.len(), inputs.len());
+    }
+
+    #[test]
+    fn non_last_array() {
+        let inputs: [Input; 2] = [
+            Input {
+                destination: "sources",
+                data_type: DataType::Path(true),
+                is_required: Some(true),
+                defaults: None,
+            },
+            Input {
+                destination: "destination",
+                data_type: DataType::Path(false),
+                is_required: Some(true),
+                defaults: None,
+            },
+        ];
+
+        let result = catch_unwind(|| create_parser_with_inputs(&inputs));
+        assert!(result.is_ok());
+        let parser = result.expect("just checked that it's Ok(_)");
+        assert_eq!(parser.positionals.len(), inputs.len());
+    }
+
     #[ignore = "parsing has not been implemented yet, so can't be tested"]
     #[test]
     fn correct_default_types() {
@@ -209,7 +267,7 @@ mod invalid {
     }
 
     #[test]
-    fn non_last_optional() {
+    fn required_after_optional() {
         let inputs: [Input; 2] = [
             Input {
                 destination: "badly_placed_optional",
@@ -220,7 +278,7 @@ mod invalid {
             Input {
                 destination: "other_positional",
                 data_type: DataType::String(false),
-                is_required: None,
+                is_required: Some(true),
                 defaults: None,
             },
         ];
@@ -230,7 +288,7 @@ mod invalid {
     }
 
     #[test]
-    fn non_last_default() {
+    fn required_after_default() {
         let inputs: [Input; 2] = [
             Input {
                 destination: "badly_placed_optional",
@@ -241,7 +299,7 @@ mod invalid {
             Input {
                 destination: "other_positional",
                 data_type: DataType::Bool(false),
-                is_required: None,
+                is_required: Some(true),
                 defaults: None,
             },
         ];
@@ -251,10 +309,10 @@ mod invalid {
     }
 
     #[test]
-    fn non_last_array() {
-        let inputs: [Input; 2] = [
+    fn two_arrays() {
+        let inputs: [Input; 3] = [
             Input {
-                destination: "badly_placed_array",
+                destination: "array",
                 data_type: DataType::String(true),
                 is_required: None,
                 defaults: None,
@@ -265,6 +323,12 @@ mod invalid {
                 is_required: None,
                 defaults: None,
             },
+            Input {
+                destination: "second_array",
+                data_type: DataType::String(true),
+                is_required: None,
+                defaults: None,
+            },
         ];
 
         let result = catch_unwind(|| create_parser_with_inputs(&inputs));
@@ -297,6 +361,92 @@ mod invalid {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn fallible_definitions() {
+        let mut parser = Parser::new();
+        parser
+            .try_positional("first", DataType::String(true))
+            .unwrap();
+        assert_eq!(
+            parser
+                .try_positional("first", DataType::String(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedDestination {
+                destination: "first".to_string()
+            }
+        );
+        assert_eq!(
+            parser
+                .try_positional("second", DataType::String(true))
+                .unwrap_err(),
+            DefinitionError::MultipleArrayPositionals {
+                destination: "second".to_string()
+            }
+        );
+
+        let positional = parser.positionals.back_mut().unwrap();
+        assert_eq!(
+            positional.try_defaults(&["a", "b"]).unwrap().defaults,
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            positional.try_is_required(true).unwrap_err(),
+            DefinitionError::RequiredWithDefault {
+                destination: "first".to_string()
+            }
+        );
+        assert_eq!(parser.positionals.len(), 1);
+
+        let mut parser = Parser::new();
+        parser.positional("sources", DataType::Path(true));
+        let positional = parser.positional("destination", DataType::Path(false));
+        assert_eq!(
+            positional.try_defaults(&["."]).unwrap_err(),
+            DefinitionError::OptionalPositionalAfterArray {
+                destination: "destination".to_string()
+            }
+        );
+
+        let mut parser = Parser::new();
+        parser
+            .positional("source", DataType::Path(false))
+            .is_required(false);
+        let positional = parser.positional("destination", DataType::Path(false));
+        assert_eq!(
+            positional.try_is_required(true).unwrap_err(),
+            DefinitionError::RequiredPositionalAfterOptional {
+                destination: "destination".to_string()
+            }
+        );
+
+        // The last positional could neither be required nor optional
+        let mut parser = Parser::new();
+        parser
+            .positional("mode", DataType::String(false))
+            .is_required(false);
+        parser.positional("sources", DataType::Path(true));
+        assert_eq!(
+            parser
+                .try_positional("destination", DataType::Path(false))
+                .unwrap_err(),
+            DefinitionError::PositionalAfterOptionalArray {
+                destination: "destination".to_string()
+            }
+        );
+        let mut parser = Parser::new();
+        parser
+            .positional("sources", DataType::Path(true))
+            .is_required(false);
+        assert_eq!(
+            parser
+                .try_positional("destination", DataType::Path(false))
+                .unwrap_err(),
+            DefinitionError::PositionalAfterOptionalArray {
+                destination: "destination".to_string()
+            }
+        );
+    }
+
     #[ignore = "parsing has not been implemented yet, so can't be tested"]
     #[test]
     fn wrong_default_types() {
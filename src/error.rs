@@ -0,0 +1,394 @@
+use std::{
+    fmt::Display,
+    num::{ParseFloatError, ParseIntError},
+};
+
+use crate::message::{English, Messages};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    UnknownOption {
+        name: String,
+        // A similar name the user might have meant
+        suggestion: Option<String>,
+    },
+    AmbiguousOption {
+        name: String,
+        candidates: Vec<String>,
+    },
+    UnknownSubParser {
+        name: String,
+        suggestion: Option<String>,
+    },
+    AmbiguousSubParser {
+        name: String,
+        candidates: Vec<String>,
+    },
+    UnexpectedPositional {
+        value: String,
+    },
+    MissingValue {
+        option: String,
+    },
+    UnexpectedValue {
+        option: String,
+    },
+    TooFewValues {
+        option: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidValue {
+        destination: String,
+        value: String,
+        reason: ValueReason,
+    },
+    DuplicateValue {
+        destination: String,
+    },
+    DuplicateKey {
+        option: String,
+        key: String,
+    },
+    MissingPositional {
+        destination: String,
+    },
+    MissingOption {
+        option: String,
+    },
+    // The origin is the response file and line that referenced the file, if any
+    ResponseFileUnreadable {
+        path: String,
+        origin: Option<(String, usize)>,
+        reason: String,
+    },
+    ResponseFileCycle {
+        path: String,
+        origin: Option<(String, usize)>,
+    },
+    ResponseFileSyntax {
+        path: String,
+        line: usize,
+        reason: SyntaxReason,
+    },
+    // A forwarded option whose replacement doesn't exist or can't take its values
+    InvalidReplacement {
+        option: String,
+    },
+    PromptFailed {
+        destination: String,
+        reason: String,
+    },
+    // An argument that was read from a response file, with the file and line
+    InResponseFile {
+        path: String,
+        line: usize,
+        error: Box<ParseError>,
+    },
+    // Not a mistake, but parsing stops at '-V' or '--version' like it does for errors
+    VersionRequested {
+        version: String,
+    },
+}
+
+// The English wording lives in the default messages, which translations override
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&English.parse_error(self))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Why a value doesn't fit the data type of its argument
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueReason {
+    InvalidUtf8,
+    InvalidInt32(ParseIntError),
+    InvalidFloat32(ParseFloatError),
+    InvalidBool,
+    // A key-value pair without a key or '='
+    MissingKey,
+    InvalidKeyUtf8,
+}
+
+impl Display for ValueReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&English.value_reason(self))
+    }
+}
+
+// Why the contents of a response file can't be split into arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxReason {
+    InvalidUtf8,
+    UnterminatedSingleQuote,
+    UnterminatedDoubleQuote,
+    TrailingBackslash,
+}
+
+impl Display for SyntaxReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&English.syntax_reason(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefinitionError {
+    OccupiedDestination {
+        destination: String,
+    },
+    InvalidOptionNames {
+        destination: String,
+    },
+    RepeatedName {
+        name: String,
+    },
+    OccupiedName {
+        name: String,
+    },
+    RequiredWithDefault {
+        destination: String,
+    },
+    TooManyDefaults {
+        destination: String,
+    },
+    InvalidDefault {
+        destination: String,
+        value: String,
+        reason: ValueReason,
+    },
+    RequiredPositionalAfterOptional {
+        destination: String,
+    },
+    MultipleArrayPositionals {
+        destination: String,
+    },
+    OptionalPositionalAfterArray {
+        destination: String,
+    },
+    PositionalAfterOptionalArray {
+        destination: String,
+    },
+    ArrayPositionalWithSubParser {
+        destination: String,
+    },
+    OptionalPositionalWithSubParser {
+        destination: String,
+    },
+    InvalidSubParserName {
+        name: String,
+    },
+    SubParserAfterArrayPositional {
+        destination: String,
+    },
+    SubParserAfterOptionalPositional {
+        destination: String,
+    },
+    BoolOptionWithoutDefault {
+        destination: String,
+    },
+    InvalidArity {
+        destination: String,
+    },
+    InvalidImplicitValue {
+        destination: String,
+        value: String,
+        reason: ValueReason,
+    },
+    UnexpectedImplicitValue {
+        destination: String,
+    },
+    InvalidValueDelimiter {
+        destination: String,
+    },
+    InvalidKeyValues {
+        destination: String,
+    },
+    DuplicateDefaultKey {
+        destination: String,
+        key: String,
+    },
+    DefaultsOutsideArity {
+        destination: String,
+    },
+    UnreachableSubParsers {
+        destination: String,
+    },
+    InvalidReplacement {
+        destination: String,
+    },
+    UnpromptableArgument {
+        destination: String,
+    },
+    UnknownHiddenName {
+        destination: String,
+        name: String,
+    },
+    AllNamesHidden {
+        destination: String,
+    },
+    InSubParser {
+        name: String,
+        error: Box<DefinitionError>,
+    },
+}
+
+impl Display for DefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefinitionError::OccupiedDestination { destination } => {
+                write!(f, "destination '{}' is occupied by another argument", destination)
+            }
+            DefinitionError::InvalidOptionNames { destination } => write!(
+                f,
+                "names of option '{}' need to be formatted like '-v', '--verbose', or '-v, --verbose, --talkative'",
+                destination
+            ),
+            DefinitionError::RepeatedName { name } => {
+                write!(f, "name '{}' is given more than once", name)
+            }
+            DefinitionError::OccupiedName { name } => {
+                write!(f, "name '{}' is occupied by another option or sub-parser", name)
+            }
+            DefinitionError::RequiredWithDefault { destination } => write!(
+                f,
+                "argument '{}' cannot be required and have a default value simultaneously",
+                destination
+            ),
+            DefinitionError::TooManyDefaults { destination } => write!(
+                f,
+                "argument '{}' is not an array and can only have one default value",
+                destination
+            ),
+            DefinitionError::InvalidDefault {
+                destination,
+                value,
+                reason,
+            } => write!(
+                f,
+                "default value '{}' of argument '{}' is invalid: {}",
+                value, destination, reason
+            ),
+            DefinitionError::RequiredPositionalAfterOptional { destination } => write!(
+                f,
+                "positional '{}' comes after an optional positional, so it cannot be required",
+                destination
+            ),
+            DefinitionError::MultipleArrayPositionals { destination } => write!(
+                f,
+                "positional array '{}' cannot be added, since there can only be one positional array",
+                destination
+            ),
+            DefinitionError::OptionalPositionalAfterArray { destination } => write!(
+                f,
+                "positional '{}' comes after a positional array, so it needs to be required",
+                destination
+            ),
+            DefinitionError::PositionalAfterOptionalArray { destination } => write!(
+                f,
+                "positional '{}' cannot be added, since the positional array before it is optional or comes after an optional positional",
+                destination
+            ),
+            DefinitionError::ArrayPositionalWithSubParser { destination } => write!(
+                f,
+                "positional array '{}' cannot be added, since a sub-parser has been added",
+                destination
+            ),
+            DefinitionError::OptionalPositionalWithSubParser { destination } => write!(
+                f,
+                "optional positional '{}' cannot be added, since a sub-parser has been added",
+                destination
+            ),
+            DefinitionError::InvalidSubParserName { name } => write!(
+                f,
+                "sub-parser name '{}' cannot be empty, start with '-', or contain whitespace",
+                name
+            ),
+            DefinitionError::SubParserAfterArrayPositional { destination } => write!(
+                f,
+                "a sub-parser cannot be added, since the last positional '{}' is an array",
+                destination
+            ),
+            DefinitionError::SubParserAfterOptionalPositional { destination } => write!(
+                f,
+                "a sub-parser cannot be added, since positional '{}' is optional",
+                destination
+            ),
+            DefinitionError::BoolOptionWithoutDefault { destination } => write!(
+                f,
+                "boolean option '{}' is not an array and needs a default value",
+                destination
+            ),
+            DefinitionError::InvalidArity { destination } => write!(
+                f,
+                "option '{}' needs an arity of at least one value, and cannot be a boolean flag",
+                destination
+            ),
+            DefinitionError::InvalidImplicitValue {
+                destination,
+                value,
+                reason,
+            } => write!(
+                f,
+                "implicit value '{}' of option '{}' is invalid: {}",
+                value, destination, reason
+            ),
+            DefinitionError::UnexpectedImplicitValue { destination } => write!(
+                f,
+                "option '{}' cannot have an implicit value, since it's a flag or has an arity",
+                destination
+            ),
+            DefinitionError::InvalidValueDelimiter { destination } => write!(
+                f,
+                "option '{}' needs to be an array without an arity to have a value delimiter, which cannot be a backslash or non-ASCII",
+                destination
+            ),
+            DefinitionError::InvalidKeyValues { destination } => write!(
+                f,
+                "option '{}' needs to be an array without an arity to take key-value pairs",
+                destination
+            ),
+            DefinitionError::DuplicateDefaultKey { destination, key } => write!(
+                f,
+                "key '{}' is given more than once in the defaults of option '{}', which rejects duplicate keys",
+                key, destination
+            ),
+            DefinitionError::DefaultsOutsideArity { destination } => write!(
+                f,
+                "the number of default values of option '{}' is outside of its arity",
+                destination
+            ),
+            DefinitionError::UnreachableSubParsers { destination } => write!(
+                f,
+                "sub-parsers are unreachable, since positional '{}' is optional or an array",
+                destination
+            ),
+            DefinitionError::InvalidReplacement { destination } => write!(
+                f,
+                "option '{}' can only be forwarded to its replacement, which needs to be another option of the same kind",
+                destination
+            ),
+            DefinitionError::UnknownHiddenName { destination, name } => write!(
+                f,
+                "option '{}' has no name '{}' that can be hidden",
+                destination, name
+            ),
+            DefinitionError::AllNamesHidden { destination } => write!(
+                f,
+                "option '{}' needs at least one name that isn't hidden",
+                destination
+            ),
+            DefinitionError::UnpromptableArgument { destination } => write!(
+                f,
+                "argument '{}' can only have a prompt if it's required and takes a single value",
+                destination
+            ),
+            DefinitionError::InSubParser { name, error } => {
+                write!(f, "in sub-parser '{}': {}", name, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
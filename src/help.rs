@@ -0,0 +1,336 @@
+use crate::{
+    argument::{option::OptionArgument, positional::PositionalArgument, DataType, Deprecation},
+    message::{Messages, Placeholder},
+    Parser,
+};
+
+pub mod man;
+pub mod markdown;
+pub mod terminal;
+
+#[cfg(test)]
+mod test;
+
+// A positional, option or sub-command as it's shown in help output. The names
+// and the value are kept apart, so that generators can style them differently.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entry {
+    pub(crate) names: Vec<String>,
+    // Includes the separator from the names, e.g. ' <FILE>' or '[=WHEN]'
+    pub(crate) value: String,
+    // Empty for sub-commands
+    pub(crate) type_name: String,
+    pub(crate) is_required: bool,
+    pub(crate) defaults: Vec<String>,
+    pub(crate) help: Option<String>,
+    pub(crate) deprecation: Option<Deprecation>,
+}
+
+impl Entry {
+    // The help text followed by notes like '[default: 1]'. Positionals
+    // already show whether they're required in their value.
+    pub(crate) fn description(&self, messages: &dyn Messages) -> String {
+        let mut parts: Vec<String> = self.help.iter().cloned().collect();
+        if self.is_required && !self.names.is_empty() {
+            parts.push(messages.required_note());
+        }
+        if !self.defaults.is_empty() {
+            parts.push(messages.default_note(&self.defaults));
+        }
+        parts.extend(self.deprecation_note(messages));
+        parts.join(" ")
+    }
+
+    pub(crate) fn deprecation_note(&self, messages: &dyn Messages) -> Option<String> {
+        let deprecation = self.deprecation.as_ref()?;
+        Some(messages.deprecated_note(deprecation.replacement.as_deref()))
+    }
+}
+
+// Everything help output shows about a parser, without its sub-commands' arguments
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Page {
+    // The program name followed by the names of the sub-commands leading here
+    pub(crate) command: String,
+    pub(crate) about: Option<String>,
+    pub(crate) usage: String,
+    pub(crate) positionals: Vec<Entry>,
+    pub(crate) options: Vec<Entry>,
+    pub(crate) sub_commands: Vec<Entry>,
+}
+
+impl Page {
+    // 'global_options' are the global options of the parsers above this one,
+    // which are shown as options of the sub-command. The messages are those
+    // of the top-level parser, since sub-parsers only get them when chosen.
+    pub(crate) fn new(
+        parser: &Parser,
+        command: &str,
+        global_options: &[&OptionArgument],
+        messages: &dyn Messages,
+    ) -> Self {
+        let positionals = parser
+            .positionals
+            .iter()
+            .filter(|positional| !positional.is_hidden)
+            .map(|positional| positional_entry(positional, messages))
+            .collect();
+        let mut options: Vec<Entry> = parser
+            .options
+            .iter()
+            .filter(|option| !option.is_inherited)
+            .chain(global_options.iter().copied())
+            .filter(|option| !option.is_hidden)
+            .map(|option| option_entry(option, messages))
+            .collect();
+        if parser.version.is_some() {
+            options.push(Entry {
+                names: vec!["-V".to_string(), "--version".to_string()],
+                value: String::new(),
+                type_name: messages.flag_type_name(),
+                is_required: false,
+                defaults: Vec::new(),
+                help: Some(messages.version_help()),
+                deprecation: None,
+            });
+        }
+        let sub_commands = visible_sub_parsers(parser)
+            .into_iter()
+            .map(|(name, child_parser)| Entry {
+                names: std::iter::once(name)
+                    .chain(&child_parser.aliases)
+                    .cloned()
+                    .collect(),
+                value: String::new(),
+                type_name: String::new(),
+                is_required: false,
+                defaults: Vec::new(),
+                help: child_parser.about.clone(),
+                deprecation: child_parser.deprecation.clone(),
+            })
+            .collect();
+
+        Self {
+            command: command.to_string(),
+            about: parser.about.clone(),
+            usage: usage(parser, command, !options.is_empty(), messages),
+            positionals,
+            options,
+            sub_commands,
+        }
+    }
+
+    // The page of the parser followed by the pages of all of its visible
+    // sub-commands, depth first and in sorted order.
+    pub(crate) fn all(parser: &Parser) -> Vec<Self> {
+        let mut pages = Vec::new();
+        collect_pages(
+            parser,
+            &program_name(parser),
+            &[],
+            &*parser.messages,
+            &mut pages,
+        );
+        pages
+    }
+
+    // The page of the sub-command at 'path', e.g. ["run", "test"] for
+    // 'tool run test', where aliases can be used instead of names.
+    pub(crate) fn find(parser: &Parser, path: &[&str]) -> Option<Self> {
+        let mut command = program_name(parser);
+        let mut global_options = Vec::new();
+        let mut current_parser = parser;
+        for name in path {
+            let child_name = current_parser.find_sub_parser_exact(name)?;
+            global_options = child_global_options(current_parser, &global_options);
+            command = format!("{} {}", command, child_name);
+            current_parser = &current_parser.child_parsers[child_name];
+        }
+        Some(Self::new(
+            current_parser,
+            &command,
+            &global_options,
+            &*parser.messages,
+        ))
+    }
+}
+
+fn collect_pages(
+    parser: &Parser,
+    command: &str,
+    global_options: &[&OptionArgument],
+    messages: &dyn Messages,
+    pages: &mut Vec<Page>,
+) {
+    pages.push(Page::new(parser, command, global_options, messages));
+    let global_options = child_global_options(parser, global_options);
+    for (name, child_parser) in visible_sub_parsers(parser) {
+        collect_pages(
+            child_parser,
+            &format!("{} {}", command, name),
+            &global_options,
+            messages,
+            pages,
+        );
+    }
+}
+
+// The global options that the sub-parsers of the parser inherit
+pub(crate) fn child_global_options<'a>(
+    parser: &'a Parser,
+    global_options: &[&'a OptionArgument],
+) -> Vec<&'a OptionArgument> {
+    let own_global_options = parser
+        .options
+        .iter()
+        .filter(|option| option.is_global && !option.is_inherited);
+    global_options
+        .iter()
+        .copied()
+        .chain(own_global_options)
+        .collect()
+}
+
+pub(crate) fn program_name(parser: &Parser) -> String {
+    if let Some(program_name) = &parser.program_name {
+        return program_name.clone();
+    }
+    std::env::args_os()
+        .next()
+        .as_ref()
+        .and_then(|executable| std::path::Path::new(executable).file_stem())
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "program".to_string())
+}
+
+// Sorted, since the order of the hash map isn't stable
+pub(crate) fn visible_sub_parsers(parser: &Parser) -> Vec<(&String, &Parser)> {
+    let mut sub_parsers: Vec<(&String, &Parser)> = parser
+        .child_parsers
+        .iter()
+        .filter(|(_, child_parser)| !child_parser.is_hidden)
+        .collect();
+    sub_parsers.sort_by_key(|(name, _)| *name);
+    sub_parsers
+}
+
+// E.g. 'program [OPTIONS] <INPUT> [FILES]... [COMMAND]'
+fn usage(parser: &Parser, command: &str, has_options: bool, messages: &dyn Messages) -> String {
+    let mut usage = command.to_string();
+    if has_options {
+        usage.push(' ');
+        usage.push_str(&messages.placeholder(Placeholder::Options));
+    }
+    for positional in parser
+        .positionals
+        .iter()
+        .filter(|positional| !positional.is_hidden)
+    {
+        usage.push(' ');
+        usage.push_str(&positional_value(positional));
+    }
+    if !visible_sub_parsers(parser).is_empty() {
+        usage.push(' ');
+        usage.push_str(&messages.placeholder(Placeholder::Command));
+    }
+    usage
+}
+
+fn positional_value(positional: &PositionalArgument) -> String {
+    let name = positional.destination.to_uppercase();
+    let value = if positional.is_required == Some(true) {
+        format!("<{}>", name)
+    } else {
+        format!("[{}]", name)
+    };
+    if positional.data_type.is_array() {
+        value + "..."
+    } else {
+        value
+    }
+}
+
+fn positional_entry(positional: &PositionalArgument, messages: &dyn Messages) -> Entry {
+    Entry {
+        names: Vec::new(),
+        value: positional_value(positional),
+        type_name: type_name(positional.data_type, messages),
+        is_required: positional.is_required == Some(true),
+        defaults: positional.defaults.clone().unwrap_or_default(),
+        help: positional.help.clone(),
+        deprecation: positional.deprecation.clone(),
+    }
+}
+
+// E.g. 'integer' or 'path[]'
+fn type_name(data_type: DataType, messages: &dyn Messages) -> String {
+    let element_type_name = messages.type_name(data_type);
+    if data_type.is_array() {
+        format!("{}[]", element_type_name)
+    } else {
+        element_type_name
+    }
+}
+
+fn option_value(option: &OptionArgument) -> String {
+    let value_name = option
+        .value_name
+        .clone()
+        .unwrap_or_else(|| option.destination.to_uppercase());
+    let value_name = match option.key_values {
+        Some(_) => format!("KEY={}", value_name),
+        None => value_name,
+    };
+
+    if matches!(option.data_type, DataType::Bool(false)) {
+        String::new()
+    } else if option.implicit_value.is_some() {
+        format!("[={}]", value_name)
+    } else if let Some((min_values, max_values)) = option.arity {
+        let needed_values = (0..min_values).map(|_| format!(" <{}>", value_name));
+        let extra_values = (min_values..max_values).map(|_| format!(" [<{}>]", value_name));
+        needed_values.chain(extra_values).collect()
+    } else if let Some(delimiter) = option.value_delimiter {
+        format!(" <{}>[{}...]", value_name, delimiter)
+    } else {
+        format!(" <{}>", value_name)
+    }
+}
+
+fn option_entry(option: &OptionArgument, messages: &dyn Messages) -> Entry {
+    let names = option.visible_names();
+
+    // E.g. 'flag', 'float map' or 'string[2]'
+    let element_type_name = messages.type_name(option.data_type);
+    let type_name = match (option.data_type, option.key_values, option.arity) {
+        (DataType::Bool(false), _, _) => messages.flag_type_name(),
+        (_, Some(_), _) => messages.map_type_name(&element_type_name),
+        (data_type, None, Some((min_values, max_values))) => {
+            let group = if min_values == max_values {
+                format!("{}[{}]", element_type_name, min_values)
+            } else {
+                format!("{}[{}..{}]", element_type_name, min_values, max_values)
+            };
+            if data_type.is_array() {
+                group + "[]"
+            } else {
+                group
+            }
+        }
+        (data_type, None, None) => type_name(data_type, messages),
+    };
+
+    Entry {
+        names,
+        value: option_value(option),
+        type_name,
+        is_required: option.is_required == Some(true),
+        // Flags always have a default, so it's only noise
+        defaults: match option.data_type {
+            DataType::Bool(false) => Vec::new(),
+            _ => option.defaults.clone().unwrap_or_default(),
+        },
+        help: option.help.clone(),
+        deprecation: option.deprecation.clone(),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    message::{Heading, Messages},
+    Parser,
+};
+
+use super::{Entry, Page};
+
+#[cfg(test)]
+mod test;
+
+// Renders the parser as a man(7) page in section 1, e.g. from a build script.
+// Every visible sub-command gets a sub-section of its own under COMMANDS.
+pub fn render(parser: &Parser) -> String {
+    let pages = Page::all(parser);
+    let main_page = &pages[0];
+    let messages = &*parser.messages;
+
+    let mut roff = String::new();
+    roff.push_str(&format!(
+        ".TH \"{}\" 1",
+        escape(&main_page.command.to_uppercase())
+    ));
+    // The version goes in the footer, where man pages name their source
+    if let Some(version) = &parser.version {
+        roff.push_str(&format!(
+            " \"\" \"{} {}\"",
+            escape(&main_page.command),
+            escape(version)
+        ));
+    }
+    roff.push('\n');
+    roff.push_str(&format!(
+        ".SH {}\n",
+        escape(&messages.heading(Heading::Name).to_uppercase())
+    ));
+    match &main_page.about {
+        Some(about) => roff.push_str(&format!(
+            "{} \\- {}\n",
+            escape(&main_page.command),
+            escape(about)
+        )),
+        None => roff.push_str(&format!("{}\n", escape(&main_page.command))),
+    }
+    roff.push_str(&format!(
+        ".SH {}\n",
+        escape(&messages.heading(Heading::Synopsis).to_uppercase())
+    ));
+    roff.push_str(&usage(main_page));
+
+    for (heading, entries) in sections(main_page) {
+        let heading = format!(
+            ".SH {}\n",
+            escape(&messages.heading(heading).to_uppercase())
+        );
+        render_entries(&mut roff, &heading, entries, messages);
+    }
+
+    for page in &pages[1..] {
+        roff.push_str(&format!(".SS \"{}\"\n", escape(&page.command)));
+        if let Some(about) = &page.about {
+            roff.push_str(&format!("{}\n", escape(about)));
+        }
+        roff.push_str(".PP\n");
+        roff.push_str(&usage(page));
+        for (heading, entries) in sections(page) {
+            let heading = format!(".PP\n\\fB{}:\\fR\n", escape(&messages.heading(heading)));
+            render_entries(&mut roff, &heading, entries, messages);
+        }
+    }
+    roff
+}
+
+fn usage(page: &Page) -> String {
+    // The command is bold, and the arguments after it are in roman
+    let arguments = &page.usage[page.command.len()..];
+    format!("\\fB{}\\fR{}\n", escape(&page.command), escape(arguments))
+}
+
+fn sections(page: &Page) -> [(Heading, &Vec<Entry>); 3] {
+    [
+        (Heading::Arguments, &page.positionals),
+        (Heading::Options, &page.options),
+        (Heading::Commands, &page.sub_commands),
+    ]
+}
+
+fn render_entries(roff: &mut String, heading: &str, entries: &[Entry], messages: &dyn Messages) {
+    if entries.is_empty() {
+        return;
+    }
+    roff.push_str(heading);
+    for entry in entries {
+        let names: Vec<String> = entry
+            .names
+            .iter()
+            .map(|name| format!("\\fB{}\\fR", escape(name)))
+            .collect();
+        roff.push_str(".TP\n");
+        roff.push_str(&names.join(", "));
+        if !entry.value.is_empty() {
+            roff.push_str(&format!("\\fI{}\\fR", escape(&entry.value)));
+        }
+        roff.push('\n');
+        let description = entry.description(messages);
+        if !description.is_empty() {
+            roff.push_str(&format!("{}\n", escape(&description)));
+        }
+    }
+}
+
+// Makes text safe to use in roff, where backslashes start escapes, dashes can
+// be turned into hyphens, and lines starting with a period or an apostrophe
+// are requests.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .lines()
+        .map(|line| {
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,70 @@
+use crate::{argument::DataType, Parser};
+
+use super::render;
+
+#[test]
+fn page() {
+    let mut parser = Parser::new();
+    parser.program_name("my-tool").about("Manages files");
+    parser
+        .option("-f, --force", "force", DataType::Bool(false))
+        .defaults(&["false"])
+        .help("Overwrite \\ replace");
+    let remove_parser = parser.sub_parser("remove, rm");
+    remove_parser.about("Removes files");
+    remove_parser
+        .positional("files", DataType::Path(true))
+        .help("The files to remove, e.g. .hidden");
+    remove_parser
+        .option("--depth", "depth", DataType::Int32(false))
+        .defaults(&["1"]);
+
+    assert_eq!(
+        render(&parser),
+        concat!(
+            ".TH \"MY\\-TOOL\" 1\n",
+            ".SH NAME\n",
+            "my\\-tool \\- Manages files\n",
+            ".SH SYNOPSIS\n",
+            "\\fBmy\\-tool\\fR [OPTIONS] [COMMAND]\n",
+            ".SH OPTIONS\n",
+            ".TP\n",
+            "\\fB\\-f\\fR, \\fB\\-\\-force\\fR\n",
+            "Overwrite \\e replace\n",
+            ".SH COMMANDS\n",
+            ".TP\n",
+            "\\fBremove\\fR, \\fBrm\\fR\n",
+            "Removes files\n",
+            ".SS \"my\\-tool remove\"\n",
+            "Removes files\n",
+            ".PP\n",
+            "\\fBmy\\-tool remove\\fR [OPTIONS] [FILES]...\n",
+            ".PP\n",
+            "\\fBArguments:\\fR\n",
+            ".TP\n",
+            "\\fI[FILES]...\\fR\n",
+            "The files to remove, e.g. .hidden\n",
+            ".PP\n",
+            "\\fBOptions:\\fR\n",
+            ".TP\n",
+            "\\fB\\-\\-depth\\fR\\fI <DEPTH>\\fR\n",
+            "[default: 1]\n",
+        )
+    );
+}
+
+#[test]
+fn escaped_requests() {
+    let mut parser = Parser::new();
+    parser.program_name("tool").about(".start\n'quote");
+    assert!(render(&parser).contains("tool \\- \\&.start\n\\&'quote\n"));
+}
+
+#[test]
+fn version_in_footer() {
+    let mut parser = Parser::new();
+    parser.program_name("tool").version("1.2.3");
+    let roff = render(&parser);
+    assert!(roff.starts_with(".TH \"TOOL\" 1 \"\" \"tool 1.2.3\"\n"));
+    assert!(roff.contains(".TP\n\\fB\\-V\\fR, \\fB\\-\\-version\\fR\nPrint the version and exit\n"));
+}
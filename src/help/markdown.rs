@@ -0,0 +1,149 @@
+use crate::{
+    message::{Column, Heading, Messages},
+    Parser,
+};
+
+use super::{Entry, Page};
+
+#[cfg(test)]
+mod test;
+
+// Renders the parser as Markdown reference documentation, with a section for
+// every visible sub-command path. Sub-commands are ordered by name and arguments
+// by definition, so the output only changes when the parser does.
+pub fn render(parser: &Parser) -> String {
+    let pages = Page::all(parser);
+    let messages = &*parser.messages;
+    let mut markdown = String::new();
+    for (idx, page) in pages.iter().enumerate() {
+        // Only the program gets a top-level heading
+        let level = if idx == 0 { "#" } else { "##" };
+        let sub_level = if idx == 0 { "##" } else { "###" };
+
+        markdown.push_str(&format!("{} {}\n\n", level, page.command));
+        if let Some(about) = &page.about {
+            markdown.push_str(&format!("{}\n\n", about));
+        }
+        markdown.push_str(&format!("```text\n{}\n```\n\n", page.usage));
+        let arguments = [
+            (Heading::Arguments, &page.positionals),
+            (Heading::Options, &page.options),
+        ];
+        for (heading, entries) in arguments {
+            let heading = messages.heading(heading);
+            render_arguments(&mut markdown, sub_level, &heading, entries, messages);
+        }
+        render_sub_commands(&mut markdown, sub_level, page, messages);
+    }
+    // Sections are separated by a blank line, but the document ends with one newline
+    markdown.truncate(markdown.trim_end().len());
+    markdown.push('\n');
+    markdown
+}
+
+fn render_arguments(
+    markdown: &mut String,
+    level: &str,
+    heading: &str,
+    entries: &[Entry],
+    messages: &dyn Messages,
+) {
+    if entries.is_empty() {
+        return;
+    }
+    markdown.push_str(&format!("{} {}\n\n", level, heading));
+    let columns = [
+        Column::Name,
+        Column::Type,
+        Column::Required,
+        Column::Default,
+        Column::Description,
+    ];
+    table_header(markdown, &columns, messages);
+    for entry in entries {
+        // The value is shown with the last name, e.g. '`-o`, `--output <FILE>`'
+        let mut names: Vec<String> = entry.names.iter().map(|name| code(name)).collect();
+        let last_name = entry.names.last().cloned().unwrap_or_default();
+        names.pop();
+        names.push(code(&(last_name + &entry.value)));
+        let defaults: Vec<String> = entry.defaults.iter().map(|default| code(default)).collect();
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            names.join(", "),
+            entry.type_name,
+            messages.yes_no(entry.is_required),
+            defaults.join(", "),
+            table_text(&description(entry, messages)),
+        ));
+    }
+    markdown.push('\n');
+}
+
+fn render_sub_commands(markdown: &mut String, level: &str, page: &Page, messages: &dyn Messages) {
+    if page.sub_commands.is_empty() {
+        return;
+    }
+    markdown.push_str(&format!(
+        "{} {}\n\n",
+        level,
+        messages.heading(Heading::Commands)
+    ));
+    table_header(markdown, &[Column::Name, Column::Description], messages);
+    for entry in &page.sub_commands {
+        // The first name links to the section of the sub-command
+        let command = format!("{} {}", page.command, entry.names[0]);
+        let mut names = vec![format!(
+            "[{}](#{})",
+            code(&entry.names[0]),
+            anchor(&command)
+        )];
+        names.extend(entry.names[1..].iter().map(|alias| code(alias)));
+        markdown.push_str(&format!(
+            "| {} | {} |\n",
+            names.join(", "),
+            table_text(&description(entry, messages)),
+        ));
+    }
+    markdown.push('\n');
+}
+
+fn table_header(markdown: &mut String, columns: &[Column], messages: &dyn Messages) {
+    let labels: Vec<String> = columns
+        .iter()
+        .map(|column| table_text(&messages.column(*column)))
+        .collect();
+    markdown.push_str(&format!("| {} |\n", labels.join(" | ")));
+    markdown.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+}
+
+// Whether an argument is required and its defaults have columns of their own
+fn description(entry: &Entry, messages: &dyn Messages) -> String {
+    entry
+        .help
+        .iter()
+        .cloned()
+        .chain(entry.deprecation_note(messages))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn code(text: &str) -> String {
+    format!("`{}`", text).replace('|', "\\|")
+}
+
+fn table_text(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+// The anchor that common Markdown renderers give a heading, e.g. '#tool-remove'
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,84 @@
+use crate::{argument::DataType, Parser};
+
+use super::render;
+
+#[test]
+fn document() {
+    let mut parser = Parser::new();
+    parser.program_name("tool").about("Manages files");
+    parser
+        .option("-o, --output", "output", DataType::Path(false))
+        .value_name("FILE")
+        .defaults(&["out.txt"])
+        .help("Where to write | append");
+    parser
+        .option("--color", "color", DataType::String(false))
+        .implicit_value("always")
+        .deprecated("renamed", Some("--colour"));
+    let list_parser = parser.sub_parser("list, ls");
+    list_parser.about("Lists files");
+    list_parser
+        .positional("patterns", DataType::String(true))
+        .help("What to list");
+    parser
+        .sub_parser("remove")
+        .positional("file", DataType::Path(false))
+        .is_required(true);
+
+    assert_eq!(
+        render(&parser),
+        r#"# tool
+
+Manages files
+
+```text
+tool [OPTIONS] [COMMAND]
+```
+
+## Options
+
+| Name | Type | Required | Default | Description |
+| --- | --- | --- | --- | --- |
+| `-o`, `--output <FILE>` | path | no | `out.txt` | Where to write \| append |
+| `--color[=COLOR]` | string | no |  | [deprecated, use '--colour' instead] |
+
+## Commands
+
+| Name | Description |
+| --- | --- |
+| [`list`](#tool-list), `ls` | Lists files |
+| [`remove`](#tool-remove) |  |
+
+## tool list
+
+Lists files
+
+```text
+tool list [PATTERNS]...
+```
+
+### Arguments
+
+| Name | Type | Required | Default | Description |
+| --- | --- | --- | --- | --- |
+| `[PATTERNS]...` | string[] | no |  | What to list |
+
+## tool remove
+
+```text
+tool remove <FILE>
+```
+
+### Arguments
+
+| Name | Type | Required | Default | Description |
+| --- | --- | --- | --- | --- |
+| `<FILE>` | path | yes |  |  |
+"#
+    );
+}
+
+#[test]
+fn anchors() {
+    assert_eq!(super::anchor("my.tool list-all"), "mytool-list-all");
+}
@@ -0,0 +1,308 @@
+use std::io::IsTerminal;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    message::{Heading, Messages},
+    Parser,
+};
+
+use super::{Entry, Page};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    // Colours are used when stdout is a terminal and NO_COLOR isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub is_bold: bool,
+    pub is_underlined: bool,
+}
+
+impl Style {
+    fn paint(&self, text: &str) -> String {
+        let mut codes = Vec::new();
+        if self.is_bold {
+            codes.push("1".to_string());
+        }
+        if self.is_underlined {
+            codes.push("4".to_string());
+        }
+        if let Some(foreground) = self.foreground {
+            codes.push((30 + foreground as u8).to_string());
+        }
+        if codes.is_empty() || text.is_empty() {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+        }
+    }
+}
+
+// The styles of the parts of help output, which are only used when colours are
+// enabled by the colour choice of the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    // E.g. 'Usage:' and 'Options:'
+    pub heading: Style,
+    // Option and sub-command names
+    pub name: Style,
+    // Value placeholders, e.g. '<FILE>'
+    pub value: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            heading: Style {
+                foreground: None,
+                is_bold: true,
+                is_underlined: true,
+            },
+            name: Style {
+                foreground: Some(Color::Green),
+                is_bold: true,
+                is_underlined: false,
+            },
+            value: Style {
+                foreground: Some(Color::Cyan),
+                is_bold: false,
+                is_underlined: false,
+            },
+        }
+    }
+}
+
+// The width used when neither COLUMNS nor the terminal tell otherwise
+const DEFAULT_WIDTH: usize = 80;
+// Descriptions move below the names when less room than this is left for them
+const MIN_DESCRIPTION_WIDTH: usize = 30;
+
+pub fn print(parser: &Parser) {
+    print!("{}", render(parser));
+}
+
+// Renders help for the parser, wrapped to the width of the terminal and
+// coloured according to the parser's colour choice.
+pub fn render(parser: &Parser) -> String {
+    render_sub_command(parser, &[]).expect("an empty path is the parser itself")
+}
+
+// Renders help for the sub-command at 'path' below the parser, e.g. ["run"]
+// for 'tool run', or None if there's no such sub-command. Aliases can be
+// used instead of names.
+pub fn render_sub_command(parser: &Parser, path: &[&str]) -> Option<String> {
+    let is_colored = match parser.color_choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let is_no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            !is_no_color && std::io::stdout().is_terminal()
+        }
+    };
+    render_with(parser, path, terminal_width(), is_colored)
+}
+
+fn terminal_width() -> usize {
+    let columns = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse::<usize>().ok())
+        .filter(|columns| *columns > 0);
+    match columns {
+        Some(columns) => columns,
+        None => match terminal_size::terminal_size() {
+            Some((terminal_size::Width(width), _)) => width as usize,
+            None => DEFAULT_WIDTH,
+        },
+    }
+}
+
+// The colours, theme and messages of the top-level parser are used for sub-commands too
+pub(crate) fn render_with(
+    parser: &Parser,
+    path: &[&str],
+    width: usize,
+    is_colored: bool,
+) -> Option<String> {
+    let theme = if is_colored {
+        parser.help_theme
+    } else {
+        Theme {
+            heading: Style::default(),
+            name: Style::default(),
+            value: Style::default(),
+        }
+    };
+    let page = Page::find(parser, path)?;
+    let messages = &*parser.messages;
+    let heading = |heading| format!("{}:", messages.heading(heading));
+
+    let mut help = String::new();
+    if let Some(about) = &page.about {
+        for line in wrap(about, width) {
+            help.push_str(&format!("{}\n", line));
+        }
+        help.push('\n');
+    }
+    let arguments = &page.usage[page.command.len()..];
+    help.push_str(&format!(
+        "{} {}{}\n",
+        theme.heading.paint(&heading(Heading::Usage)),
+        theme.name.paint(&page.command),
+        arguments
+    ));
+
+    let sections = [
+        (Heading::Arguments, &page.positionals),
+        (Heading::Options, &page.options),
+        (Heading::Commands, &page.sub_commands),
+    ];
+    for (section, entries) in sections {
+        render_entries(
+            &mut help,
+            &heading(section),
+            entries,
+            width,
+            &theme,
+            messages,
+        );
+    }
+    Some(help)
+}
+
+fn render_entries(
+    help: &mut String,
+    heading: &str,
+    entries: &[Entry],
+    width: usize,
+    theme: &Theme,
+    messages: &dyn Messages,
+) {
+    if entries.is_empty() {
+        return;
+    }
+    help.push('\n');
+    help.push_str(&format!("{}\n", theme.heading.paint(heading)));
+
+    // Long names line up with the long names of options that have short ones
+    let has_short_names = entries
+        .iter()
+        .any(|entry| entry.names.iter().any(|name| !name.starts_with("--")));
+    let synopses: Vec<(String, String)> = entries
+        .iter()
+        .map(|entry| {
+            let indent = match entry.names.first() {
+                Some(name) if has_short_names && name.starts_with("--") => "    ",
+                _ => "",
+            };
+            let plain = format!("{}{}{}", indent, entry.names.join(", "), entry.value);
+            let names: Vec<String> = entry
+                .names
+                .iter()
+                .map(|name| theme.name.paint(name))
+                .collect();
+            let styled = format!(
+                "{}{}{}",
+                indent,
+                names.join(", "),
+                theme.value.paint(&entry.value)
+            );
+            (plain, styled)
+        })
+        .collect();
+
+    let synopsis_width = synopses
+        .iter()
+        .map(|(plain, _)| plain.width())
+        .max()
+        .unwrap_or(0);
+    // Two spaces before the synopsis and two between it and the description
+    let description_column = 2 + synopsis_width + 2;
+    let is_stacked = description_column + MIN_DESCRIPTION_WIDTH > width;
+    let description_indent = if is_stacked { 10 } else { description_column };
+
+    for (entry, (plain, styled)) in entries.iter().zip(synopses) {
+        help.push_str(&format!("  {}", styled));
+        let lines = wrap(
+            &entry.description(messages),
+            width.saturating_sub(description_indent),
+        );
+        for (idx, line) in lines.iter().enumerate() {
+            if idx == 0 && !is_stacked {
+                let padding = synopsis_width - plain.width() + 2;
+                help.push_str(&format!("{}{}", " ".repeat(padding), line));
+            } else {
+                help.push_str(&format!("\n{}{}", " ".repeat(description_indent), line));
+            }
+        }
+        help.push('\n');
+    }
+}
+
+// Splits text into lines of at most 'width' terminal columns, only breaking
+// words that don't fit on a line of their own. Wide characters, e.g. Japanese
+// ones, take up two columns.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for mut word in paragraph.split_whitespace() {
+            while word.width() > width {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let split_idx = split_idx_at_width(word, width);
+                lines.push(word[..split_idx].to_string());
+                word = &word[split_idx..];
+            }
+            if word.is_empty() {
+                continue;
+            }
+            let line_width = line.width();
+            if line_width > 0 && line_width + 1 + word.width() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+// The byte index after the characters that fit in 'width' columns, which
+// includes at least one character, so that wrapping always makes progress.
+fn split_idx_at_width(text: &str, width: usize) -> usize {
+    let mut used_width = 0;
+    for (idx, c) in text.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if idx > 0 && used_width + char_width > width {
+            return idx;
+        }
+        used_width += char_width;
+    }
+    text.len()
+}
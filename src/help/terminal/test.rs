@@ -0,0 +1,147 @@
+use crate::{argument::DataType, Parser};
+
+use super::{render_with, wrap, Color, ColorChoice, Style, Theme};
+
+fn create_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser
+        .program_name("tool")
+        .about("Copies files from one place to another");
+    parser
+        .positional("files", DataType::Path(true))
+        .help("The files to copy");
+    parser
+        .option("-o, --output", "output", DataType::Path(false))
+        .value_name("DIR")
+        .defaults(&["."])
+        .help("Where the copies are written to");
+    parser
+        .option("--force", "force", DataType::Bool(false))
+        .defaults(&["false"])
+        .help("Overwrite files that already exist");
+    parser
+}
+
+#[test]
+fn aligned_columns() {
+    let parser = create_parser();
+    assert_eq!(
+        render_with(&parser, &[], 80, false).unwrap(),
+        concat!(
+            "Copies files from one place to another\n",
+            "\n",
+            "Usage: tool [OPTIONS] [FILES]...\n",
+            "\n",
+            "Arguments:\n",
+            "  [FILES]...  The files to copy\n",
+            "\n",
+            "Options:\n",
+            "  -o, --output <DIR>  Where the copies are written to [default: .]\n",
+            "      --force         Overwrite files that already exist\n",
+        )
+    );
+}
+
+#[test]
+fn wrapped_descriptions() {
+    let parser = create_parser();
+    let help = render_with(&parser, &[], 56, false).unwrap();
+    assert!(help.contains(concat!(
+        "  -o, --output <DIR>  Where the copies are written to\n",
+        "                      [default: .]\n",
+    )));
+
+    // Descriptions move below the names when there's too little room
+    let help = render_with(&parser, &[], 44, false).unwrap();
+    assert!(help.contains(concat!(
+        "  -o, --output <DIR>\n",
+        "          Where the copies are written to\n",
+        "          [default: .]\n",
+    )));
+}
+
+#[test]
+fn colors() {
+    let mut parser = create_parser();
+    parser.color(ColorChoice::Always).help_theme(Theme {
+        heading: Style::default(),
+        name: Style {
+            foreground: Some(Color::Red),
+            is_bold: true,
+            is_underlined: false,
+        },
+        value: Style::default(),
+    });
+    let help = render_with(&parser, &[], 80, true).unwrap();
+    assert!(
+        help.contains("Options:\n  \x1b[1;31m-o\x1b[0m, \x1b[1;31m--output\x1b[0m <DIR>  Where")
+    );
+    assert!(!render_with(&parser, &[], 80, false)
+        .unwrap()
+        .contains('\x1b'));
+}
+
+#[test]
+fn wrapped_words() {
+    assert_eq!(wrap("a bb ccc", 4), ["a bb", "ccc"]);
+    assert_eq!(wrap("abcdefg h", 3), ["abc", "def", "g h"]);
+    assert_eq!(wrap("a\nb", 10), ["a", "b"]);
+}
+
+#[test]
+fn wide_characters() {
+    // Every character takes up two columns
+    assert_eq!(wrap("日本語のテキスト", 6), ["日本語", "のテキ", "スト"]);
+    assert_eq!(wrap("日本 語", 5), ["日本", "語"]);
+
+    let mut parser = Parser::new();
+    parser.program_name("tool");
+    parser
+        .option("-o", "output", DataType::Path(false))
+        .value_name("出力")
+        .help("書き込み先");
+    parser
+        .option("--force", "force", DataType::Bool(false))
+        .defaults(&["false"])
+        .help("上書きする");
+    assert_eq!(
+        render_with(&parser, &[], 80, false).unwrap(),
+        concat!(
+            "Usage: tool [OPTIONS]\n",
+            "\n",
+            "Options:\n",
+            "  -o <出力>    書き込み先\n",
+            "      --force  上書きする\n",
+        )
+    );
+}
+
+#[test]
+fn sub_command() {
+    let mut parser = Parser::new();
+    parser.program_name("tool");
+    parser
+        .option("-v, --verbose", "verbose", DataType::Bool(false))
+        .defaults(&["false"])
+        .global(true)
+        .help("Print more");
+    let run_parser = parser.sub_parser("run, r").about("Runs a script");
+    run_parser
+        .positional("script", DataType::Path(false))
+        .is_required(true);
+    assert_eq!(
+        render_with(&parser, &["r"], 80, false).unwrap(),
+        concat!(
+            "Runs a script\n",
+            "\n",
+            "Usage: tool run [OPTIONS] <SCRIPT>\n",
+            "\n",
+            "Arguments:\n",
+            "  <SCRIPT>\n",
+            "\n",
+            "Options:\n",
+            "  -v, --verbose  Print more\n",
+        )
+    );
+    assert_eq!(render_with(&parser, &["walk"], 80, false), None);
+}
@@ -0,0 +1,183 @@
+use crate::{
+    argument::{option::DuplicateKeys, DataType},
+    message::English,
+    Parser,
+};
+
+use super::{Entry, Page};
+
+fn create_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.program_name("tool").about("Does things");
+    parser
+        .positional("input", DataType::Path(false))
+        .is_required(true)
+        .help("The file to read");
+    parser
+        .option("-v, --verbose", "verbose", DataType::Bool(false))
+        .defaults(&["false"])
+        .hidden_names(&["-v"]);
+    parser
+        .option("-o, --output", "output", DataType::Path(false))
+        .value_name("FILE")
+        .defaults(&["out.txt"])
+        .help("Where to write");
+    parser
+        .option("--color", "color", DataType::String(false))
+        .implicit_value("always")
+        .deprecated("use '--colour'", Some("--colour"));
+    parser
+        .option("--size", "size", DataType::Int32(false))
+        .arity_range(1, 2)
+        .is_required(true);
+    parser
+        .option("-D", "defines", DataType::String(true))
+        .key_values(Some(DuplicateKeys::Reject))
+        .value_delimiter(',');
+    parser
+        .option("--secret", "secret", DataType::String(false))
+        .hidden(true);
+    parser.sub_parser("remove, rm").about("Removes things");
+    parser.sub_parser("add").hidden(true);
+    parser
+}
+
+#[test]
+fn page() {
+    let parser = create_parser();
+    let page = Page::new(&parser, "tool", &[], &English);
+    assert_eq!(page.usage, "tool [OPTIONS] <INPUT> [COMMAND]");
+    assert_eq!(page.about.as_deref(), Some("Does things"));
+    assert_eq!(
+        page.positionals,
+        [Entry {
+            names: Vec::new(),
+            value: "<INPUT>".to_string(),
+            type_name: "path".to_string(),
+            is_required: true,
+            defaults: Vec::new(),
+            help: Some("The file to read".to_string()),
+            deprecation: None,
+        }]
+    );
+    assert_eq!(
+        page.positionals[0].description(&English),
+        "The file to read"
+    );
+    let options: Vec<(String, &str, &str, String)> = page
+        .options
+        .iter()
+        .map(|entry| {
+            (
+                entry.names.join(", "),
+                entry.value.as_str(),
+                entry.type_name.as_str(),
+                entry.description(&English),
+            )
+        })
+        .collect();
+    assert_eq!(
+        options,
+        [
+            ("--verbose".to_string(), "", "flag", String::new()),
+            (
+                "-o, --output".to_string(),
+                " <FILE>",
+                "path",
+                "Where to write [default: out.txt]".to_string()
+            ),
+            (
+                "--color".to_string(),
+                "[=COLOR]",
+                "string",
+                "[deprecated, use '--colour' instead]".to_string()
+            ),
+            (
+                "--size".to_string(),
+                " <SIZE> [<SIZE>]",
+                "integer[1..2]",
+                "[required]".to_string()
+            ),
+            (
+                "-D".to_string(),
+                " <KEY=DEFINES>[,...]",
+                "string map",
+                String::new()
+            ),
+        ]
+    );
+    let sub_commands: Vec<(String, String)> = page
+        .sub_commands
+        .iter()
+        .map(|entry| (entry.names.join(", "), entry.description(&English)))
+        .collect();
+    assert_eq!(
+        sub_commands,
+        [("remove, rm".to_string(), "Removes things".to_string())]
+    );
+}
+
+#[test]
+fn all_pages() {
+    let mut parser = create_parser();
+    parser
+        .sub_parser("list")
+        .sub_parser("all")
+        .positional("patterns", DataType::String(true));
+    let pages = Page::all(&parser);
+    let commands: Vec<&str> = pages.iter().map(|page| page.command.as_str()).collect();
+    assert_eq!(
+        commands,
+        ["tool", "tool list", "tool list all", "tool remove"]
+    );
+    assert_eq!(pages[1].usage, "tool list [COMMAND]");
+    assert_eq!(pages[2].usage, "tool list all [PATTERNS]...");
+}
+
+#[test]
+fn global_options_on_sub_command_pages() {
+    let mut parser = Parser::new();
+    parser.program_name("tool");
+    // Added before the global options, so it only inherits them when parsing
+    parser.sub_parser("run").sub_parser("test");
+    parser
+        .option("-v, --verbose", "verbose", DataType::Bool(false))
+        .defaults(&["false"])
+        .global(true);
+    parser
+        .option("--token", "token", DataType::String(false))
+        .hidden(true)
+        .global(true);
+    parser.sub_parser("build");
+    let pages = Page::all(&parser);
+
+    for page in &pages[1..] {
+        let names: Vec<String> = page
+            .options
+            .iter()
+            .map(|entry| entry.names.join(", "))
+            .collect();
+        assert_eq!(names, ["-v, --verbose"], "{}", page.command);
+        assert!(page.usage.contains("[OPTIONS]"));
+    }
+    assert!(crate::help::markdown::render(&parser)
+        .contains("## tool run test\n\n```text\ntool run test [OPTIONS]\n```\n\n### Options"));
+}
+
+#[test]
+fn propagated_version_on_sub_command_pages() {
+    let mut parser = Parser::new();
+    parser.program_name("tool").sub_parser("run");
+    parser.version("1.2.3").propagate_version(true);
+    parser.sub_parser("build");
+
+    for path in [["run"], ["build"]] {
+        let page = Page::find(&parser, &path).unwrap();
+        let names: Vec<String> = page
+            .options
+            .iter()
+            .map(|entry| entry.names.join(", "))
+            .collect();
+        assert_eq!(names, ["-V, --version"], "{}", page.command);
+    }
+}
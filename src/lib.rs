@@ -1,24 +1,77 @@
 use std::{
     collections::{HashMap, VecDeque},
-    env::Args,
-    iter::Skip,
+    ffi::{OsStr, OsString},
+    sync::Arc,
 };
 
-use argument::{option::OptionArgument, positional::PositionalArgument, DataType};
-use result::{ParseResult, ParseValue};
+use argument::{
+    option::{DuplicateKeys, OptionArgument},
+    positional::PositionalArgument,
+    DataType, Deprecation,
+};
+use error::{DefinitionError, ParseError};
+use help::terminal::{ColorChoice, Theme};
+use message::{ArgumentKind, English, Messages};
+use prompt::{Prompter, TerminalPrompter};
+use response_file::ResponseFileFormat;
+use result::{ParseResult, ParseValue, ValueSource};
 
 pub mod argument;
+pub mod error;
+pub mod help;
+pub mod message;
+pub mod prompt;
+pub mod response_file;
 pub mod result;
 
+#[cfg(test)]
+mod test;
+
 // TODO: make sure all checks of is_required and defaults are correct
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Parser {
     // Argument variables
     positionals: VecDeque<PositionalArgument>,
     options: Vec<OptionArgument>,
     // Sub parser variables
-    parent_parser: Option<*const Parser>,
     child_parsers: HashMap<String, Parser>,
+    aliases: Vec<String>,
+    is_hidden: bool,
+    deprecation: Option<Deprecation>,
+    // Help variables
+    program_name: Option<String>,
+    about: Option<String>,
+    color_choice: ColorChoice,
+    help_theme: Theme,
+    version: Option<String>,
+    long_version: Option<String>,
+    is_version_inherited: bool,
+    // Shared with the sub-parsers when they're chosen
+    messages: Arc<dyn Messages>,
+    prompter: Arc<dyn Prompter>,
+    // Settings
+    allow_abbreviations: bool,
+    allow_sub_parser_abbreviations: bool,
+    response_file_format: Option<ResponseFileFormat>,
+    propagate_version: bool,
+}
+
+// The names of the option that is added together with a version
+const VERSION_NAMES: [&str; 2] = ["-V", "--version"];
+// Given together with the version option to print the long version instead
+const VERBOSE_NAME: &str = "--verbose";
+
+// What an option name on the command line stands for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OptionMatch {
+    Option(usize),
+    Version,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Parser {
@@ -26,40 +79,227 @@ impl Parser {
         Self {
             positionals: VecDeque::new(),
             options: Vec::new(),
-            parent_parser: None,
             child_parsers: HashMap::new(),
+            aliases: Vec::new(),
+            is_hidden: false,
+            deprecation: None,
+            program_name: None,
+            about: None,
+            color_choice: ColorChoice::Auto,
+            help_theme: Theme::default(),
+            version: None,
+            long_version: None,
+            is_version_inherited: false,
+            messages: Arc::new(English),
+            prompter: Arc::new(TerminalPrompter),
+            allow_abbreviations: false,
+            allow_sub_parser_abbreviations: false,
+            response_file_format: None,
+            propagate_version: false,
         }
     }
 
-    // TODO: if positionals are exhausted, choose child parser
+    // The first of the comma separated names is the one the sub-parser is
+    // known as in the parse result, the rest are aliases, e.g. 'remove, rm'.
+    pub fn sub_parser(&mut self, names: &str) -> &mut Self {
+        self.try_sub_parser(names)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
-    pub fn sub_parser(&mut self, name: &str) -> &mut Self {
-        if self.child_parsers.contains_key(name) {
-            panic!("a sub-parser already has the name '{}'", name);
-        } else if let Some(last_positional) = self.positionals.back() {
-            if last_positional.data_type.is_array() {
-                panic!(
-                    "a sub-parser cannot be added, since the last positional '{}' is an array",
-                    &last_positional.destination
-                );
-            } else if last_positional.is_required == Some(false)
-                || last_positional.defaults.is_some()
-            {
-                panic!(
-                    "a sub-parser cannot be added, since the last positional '{}' is optional",
-                    &last_positional.destination
-                );
+    pub fn try_sub_parser(&mut self, names: &str) -> Result<&mut Self, DefinitionError> {
+        let names: Vec<&str> = names.split(',').map(|name| name.trim()).collect();
+        for (idx, name) in names.iter().enumerate() {
+            if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+                return Err(DefinitionError::InvalidSubParserName {
+                    name: name.to_string(),
+                });
+            } else if names[..idx].contains(name) {
+                return Err(DefinitionError::RepeatedName {
+                    name: name.to_string(),
+                });
+            } else if self.find_sub_parser_exact(name).is_some() {
+                return Err(DefinitionError::OccupiedName {
+                    name: name.to_string(),
+                });
             }
         }
 
-        let child_parser = Self {
-            positionals: VecDeque::new(),
-            options: Vec::new(),
-            parent_parser: Some(self as *const Self),
-            child_parsers: HashMap::new(),
+        if let Some(array_positional) = self
+            .positionals
+            .iter()
+            .find(|positional| positional.data_type.is_array())
+        {
+            return Err(DefinitionError::SubParserAfterArrayPositional {
+                destination: array_positional.destination.clone(),
+            });
+        }
+        // Otherwise whether an argument is a value or a sub-command would
+        // depend on how many arguments there are.
+        if let Some(optional_positional) = self
+            .positionals
+            .iter()
+            .find(|positional| positional.is_optional())
+        {
+            return Err(DefinitionError::SubParserAfterOptionalPositional {
+                destination: optional_positional.destination.clone(),
+            });
+        }
+
+        let mut child_parser = Self {
+            aliases: names[1..].iter().map(|alias| alias.to_string()).collect(),
+            ..Self::new()
         };
-        self.child_parsers.insert(name.to_string(), child_parser);
-        self.child_parsers.get_mut(name).expect("was just added")
+        // Inherited right away, so that options of the sub-parser can't clash with them
+        child_parser
+            .inherit_global_options(&self.options)
+            .expect("a new sub-parser has no options");
+        if self.propagate_version && self.version.is_some() {
+            child_parser.inherit_version(&self.version, &self.long_version);
+        }
+        self.child_parsers
+            .insert(names[0].to_string(), child_parser);
+        Ok(self
+            .child_parsers
+            .get_mut(names[0])
+            .expect("was just added"))
+    }
+
+    // Hidden sub-parsers can still be chosen by their names, but are left out of
+    // help output and aren't chosen by abbreviations.
+    pub fn hidden(&mut self, is_hidden: bool) -> &mut Self {
+        self.is_hidden = is_hidden;
+        self
+    }
+
+    // The name used in help output, which defaults to the file name of the
+    // executable. Sub-parsers are always known by their own names.
+    pub fn program_name(&mut self, program_name: &str) -> &mut Self {
+        self.program_name = Some(program_name.to_string());
+        self
+    }
+
+    // A short description of what the program or sub-command does
+    pub fn about(&mut self, about: &str) -> &mut Self {
+        self.about = Some(about.to_string());
+        self
+    }
+
+    // Overrides whether help output is coloured, which by default depends
+    // on NO_COLOR and whether stdout is a terminal.
+    pub fn color(&mut self, color_choice: ColorChoice) -> &mut Self {
+        self.color_choice = color_choice;
+        self
+    }
+
+    pub fn help_theme(&mut self, theme: Theme) -> &mut Self {
+        self.help_theme = theme;
+        self
+    }
+
+    // Adds '-V, --version', which makes parsing stop with ParseError::VersionRequested,
+    // so that parse_args prints e.g. 'program 1.2.3' and exits. Together with
+    // '--verbose' on either side, e.g. 'program --version --verbose', the long
+    // version is printed instead if there is one. Like other options, '--version'
+    // can be abbreviated if abbreviations are allowed.
+    pub fn version(&mut self, version: &str) -> &mut Self {
+        self.try_version(version)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_version(&mut self, version: &str) -> Result<&mut Self, DefinitionError> {
+        for name in VERSION_NAMES {
+            if self.options.iter().any(|option| option.has_name(name)) {
+                return Err(DefinitionError::OccupiedName {
+                    name: name.to_string(),
+                });
+            }
+        }
+        self.version = Some(version.to_string());
+        self.is_version_inherited = false;
+        self.propagate_version_to_children();
+        Ok(self)
+    }
+
+    // Printed instead of the version for e.g. '--version --verbose' or
+    // '--verbose -V', e.g. to include build details.
+    pub fn long_version(&mut self, long_version: &str) -> &mut Self {
+        self.long_version = Some(long_version.to_string());
+        self.propagate_version_to_children();
+        self
+    }
+
+    // Gives sub-parsers without a version of their own the version of this parser,
+    // including the sub-parsers that are only defined afterwards.
+    pub fn propagate_version(&mut self, propagate_version: bool) -> &mut Self {
+        self.propagate_version = propagate_version;
+        self.propagate_version_to_children();
+        self
+    }
+
+    fn propagate_version_to_children(&mut self) {
+        if !self.propagate_version || self.version.is_none() {
+            return;
+        }
+        for child_parser in self.child_parsers.values_mut() {
+            child_parser.inherit_version(&self.version, &self.long_version);
+        }
+    }
+
+    fn inherit_version(&mut self, version: &Option<String>, long_version: &Option<String>) {
+        let has_own_version = self.version.is_some() && !self.is_version_inherited;
+        let is_occupied = VERSION_NAMES
+            .iter()
+            .any(|name| self.options.iter().any(|option| option.has_name(name)));
+        if has_own_version || is_occupied {
+            return;
+        }
+        self.version = version.clone();
+        self.long_version = long_version.clone();
+        self.is_version_inherited = true;
+        self.propagate_version = true;
+        self.propagate_version_to_children();
+    }
+
+    pub(crate) fn has_version_name(&self, name: &str) -> bool {
+        self.version.is_some() && VERSION_NAMES.contains(&name)
+    }
+
+    fn has_version_arg(&self, raw_args: &VecDeque<(usize, OsString)>) -> bool {
+        raw_args.iter().any(|(_, raw_arg)| {
+            let (name, attached_value) = split_attached_value(raw_arg);
+            attached_value.is_none() && self.find_option(&name) == Ok(OptionMatch::Version)
+        })
+    }
+
+    fn version_requested(&self, is_verbose: bool) -> ParseError {
+        let version = match (&self.long_version, &self.version) {
+            (Some(long_version), _) if is_verbose => long_version,
+            (_, Some(version)) => version,
+            (_, None) => unreachable!("only requested when there's a version"),
+        };
+        ParseError::VersionRequested {
+            version: format!("{} {}", help::program_name(self), version),
+        }
+    }
+
+    // Replaces the English errors, warnings and help output with a translation
+    pub fn messages(&mut self, messages: impl Messages + 'static) -> &mut Self {
+        self.messages = Arc::new(messages);
+        self
+    }
+
+    // Replaces how missing arguments with a prompt are asked for, which is
+    // on the terminal by default. Sub-parsers use the prompter of their parent.
+    pub fn prompter(&mut self, prompter: impl Prompter + 'static) -> &mut Self {
+        self.prompter = Arc::new(prompter);
+        self
+    }
+
+    // Choosing a deprecated sub-parser adds a warning to the parse result,
+    // which points at the replacement sub-parser if there is one.
+    pub fn deprecated(&mut self, note: &str, replacement: Option<&str>) -> &mut Self {
+        self.deprecation = Some(Deprecation::new(note, replacement));
+        self
     }
 
     pub fn positional(
@@ -67,9 +307,18 @@ impl Parser {
         destination: &str,
         data_type: DataType,
     ) -> &mut PositionalArgument {
-        let positional = PositionalArgument::new(self, destination, data_type);
+        self.try_positional(destination, data_type)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_positional(
+        &mut self,
+        destination: &str,
+        data_type: DataType,
+    ) -> Result<&mut PositionalArgument, DefinitionError> {
+        let positional = PositionalArgument::new(self, destination, data_type)?;
         self.positionals.push_back(positional);
-        self.positionals.back_mut().expect("was just added")
+        Ok(self.positionals.back_mut().expect("was just added"))
     }
 
     pub fn option(
@@ -78,20 +327,367 @@ impl Parser {
         destination: &str,
         data_type: DataType,
     ) -> &mut OptionArgument {
-        let option = OptionArgument::new(self, names, destination, data_type);
+        self.try_option(names, destination, data_type)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_option(
+        &mut self,
+        names: &str,
+        destination: &str,
+        data_type: DataType,
+    ) -> Result<&mut OptionArgument, DefinitionError> {
+        let option = OptionArgument::new(self, names, destination, data_type)?;
+        // An own option of the sub-parser takes precedence over an inherited version
+        if self.is_version_inherited && VERSION_NAMES.iter().any(|name| option.has_name(name)) {
+            self.version = None;
+            self.long_version = None;
+            self.is_version_inherited = false;
+        }
         self.options.push(option);
-        self.options.last_mut().expect("was just added")
+        Ok(self.options.last_mut().expect("was just added"))
+    }
+
+    // Accepts unique prefixes of long names, e.g. '--verb' for '--verbose'.
+    // An exact match always takes precedence over abbreviations. Sub-parsers
+    // use the setting of their parent.
+    pub fn allow_abbreviations(&mut self, allow_abbreviations: bool) -> &mut Self {
+        self.allow_abbreviations = allow_abbreviations;
+        self
+    }
+
+    // Accepts unique prefixes of sub-parser names and aliases, e.g. 'rem' for 'remove'.
+    // An exact match always takes precedence over abbreviations. Sub-parsers
+    // use the setting of their parent.
+    pub fn allow_sub_parser_abbreviations(&mut self, allow_abbreviations: bool) -> &mut Self {
+        self.allow_sub_parser_abbreviations = allow_abbreviations;
+        self
+    }
+
+    // Replaces '@path' arguments with the arguments in the file at 'path'
+    // before parsing. Only has an effect on the top-level parser. Errors about
+    // arguments from a file say which file and line they were read from.
+    pub fn response_files(&mut self, format: Option<ResponseFileFormat>) -> &mut Self {
+        self.response_file_format = format;
+        self
+    }
+
+    // Replaces the inherited copies of global options with the current ones
+    pub(crate) fn inherit_global_options(
+        &mut self,
+        parent_options: &[OptionArgument],
+    ) -> Result<(), DefinitionError> {
+        self.options.retain(|option| !option.is_inherited);
+        for parent_option in parent_options.iter().filter(|option| option.is_global) {
+            let is_occupied_destination = self
+                .options
+                .iter()
+                .map(|option| &option.destination)
+                .chain(
+                    self.positionals
+                        .iter()
+                        .map(|positional| &positional.destination),
+                )
+                .any(|destination| destination == &parent_option.destination);
+            if is_occupied_destination {
+                return Err(DefinitionError::OccupiedDestination {
+                    destination: parent_option.destination.clone(),
+                });
+            }
+            let parent_names = parent_option
+                .short_names
+                .iter()
+                .map(|short_name| format!("-{}", short_name))
+                .chain(
+                    parent_option
+                        .long_names
+                        .iter()
+                        .map(|long_name| format!("--{}", long_name)),
+                );
+            for name in parent_names {
+                if self.options.iter().any(|option| option.has_name(&name)) {
+                    return Err(DefinitionError::OccupiedName { name });
+                }
+            }
+
+            let mut option = parent_option.clone();
+            option.is_inherited = true;
+            self.options.push(option);
+        }
+        Ok(())
+    }
+
+    // All option names and destinations used in the sub-parsers, recursively
+    pub(crate) fn collect_sub_parser_occupants(
+        &self,
+        names: &mut Vec<String>,
+        destinations: &mut Vec<String>,
+    ) {
+        for child_parser in self.child_parsers.values() {
+            for option in &child_parser.options {
+                names.extend(option.short_names.iter().map(|name| format!("-{}", name)));
+                names.extend(option.long_names.iter().map(|name| format!("--{}", name)));
+                destinations.push(option.destination.clone());
+            }
+            destinations.extend(
+                child_parser
+                    .positionals
+                    .iter()
+                    .map(|positional| positional.destination.clone()),
+            );
+            child_parser.collect_sub_parser_occupants(names, destinations);
+        }
+    }
+
+    // Checks the invariants that can only be checked once the whole
+    // parser tree has been defined. Run before parsing in debug builds.
+    pub fn validate(&self) -> Result<(), Vec<DefinitionError>> {
+        let mut errors = Vec::new();
+        self.collect_definition_errors(&mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn collect_definition_errors(&self, errors: &mut Vec<DefinitionError>) {
+        for option in self.options.iter().filter(|option| !option.is_inherited) {
+            if matches!(option.data_type, DataType::Bool(false)) && option.defaults.is_none() {
+                errors.push(DefinitionError::BoolOptionWithoutDefault {
+                    destination: option.destination.clone(),
+                });
+            }
+            let is_single_valued = !matches!(option.data_type, DataType::Bool(false))
+                && !option.data_type.is_array()
+                && option.arity.is_none()
+                && option.key_values.is_none();
+            if option.prompt.is_some() && (option.is_required != Some(true) || !is_single_valued) {
+                errors.push(DefinitionError::UnpromptableArgument {
+                    destination: option.destination.clone(),
+                });
+            }
+            if option.is_forwarded && self.find_replacement(option).is_none() {
+                errors.push(DefinitionError::InvalidReplacement {
+                    destination: option.destination.clone(),
+                });
+            }
+        }
+
+        let mut is_after_optional = false;
+        let mut is_after_array = false;
+        for positional in &self.positionals {
+            if is_after_array && is_after_optional {
+                errors.push(DefinitionError::PositionalAfterOptionalArray {
+                    destination: positional.destination.clone(),
+                });
+            }
+            is_after_optional |= positional.is_optional();
+            is_after_array |= positional.data_type.is_array();
+            if positional.prompt.is_some()
+                && (positional.is_required != Some(true) || positional.data_type.is_array())
+            {
+                errors.push(DefinitionError::UnpromptableArgument {
+                    destination: positional.destination.clone(),
+                });
+            }
+        }
+
+        let unreachable_positional = self
+            .positionals
+            .iter()
+            .find(|positional| positional.is_optional() || positional.data_type.is_array());
+        if let Some(positional) = unreachable_positional {
+            if !self.child_parsers.is_empty() {
+                errors.push(DefinitionError::UnreachableSubParsers {
+                    destination: positional.destination.clone(),
+                });
+            }
+        }
+
+        // Sorted, since the order of the hash map isn't stable
+        let mut child_names: Vec<&String> = self.child_parsers.keys().collect();
+        child_names.sort();
+        for child_name in child_names {
+            let mut child_parser = self.child_parsers[child_name].clone();
+            let mut child_errors = Vec::new();
+            if let Err(err) = child_parser.inherit_global_options(&self.options) {
+                child_errors.push(err);
+            }
+            child_parser.collect_definition_errors(&mut child_errors);
+            errors.extend(
+                child_errors
+                    .into_iter()
+                    .map(|err| DefinitionError::InSubParser {
+                        name: child_name.clone(),
+                        error: Box::new(err),
+                    }),
+            );
+        }
+    }
+
+    // The option that a deprecated option is forwarded to, which needs to be
+    // able to take the same values.
+    fn find_replacement(&self, option: &OptionArgument) -> Option<usize> {
+        let replacement = option.deprecation.as_ref()?.replacement.as_ref()?;
+        self.options.iter().position(|other| {
+            other.has_name(replacement)
+                && other.destination != option.destination
+                && other.data_type == option.data_type
+                && other.arity == option.arity
+                && other.key_values == option.key_values
+                && other.value_delimiter == option.value_delimiter
+        })
+    }
+
+    fn find_sub_parser_exact(&self, name: &str) -> Option<&String> {
+        self.child_parsers
+            .iter()
+            .find(|(child_name, child_parser)| {
+                *child_name == name || child_parser.aliases.iter().any(|alias| alias == name)
+            })
+            .map(|(child_name, _)| child_name)
+    }
+
+    fn find_sub_parser(&self, name: &str) -> Result<String, ParseError> {
+        if let Some(child_name) = self.find_sub_parser_exact(name) {
+            return Ok(child_name.clone());
+        }
+
+        let mut candidates: Vec<String> = if self.allow_sub_parser_abbreviations {
+            self.child_parsers
+                .iter()
+                // Hidden sub-parsers can only be chosen by their full name
+                .filter(|(child_name, child_parser)| {
+                    !child_parser.is_hidden
+                        && (child_name.starts_with(name)
+                            || child_parser
+                                .aliases
+                                .iter()
+                                .any(|alias| alias.starts_with(name)))
+                })
+                .map(|(child_name, _)| child_name.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        // Sorted, since the order of the hash map isn't stable
+        candidates.sort();
+
+        match candidates.len() {
+            0 => {
+                let visible_names = self
+                    .child_parsers
+                    .iter()
+                    .filter(|(_, child_parser)| !child_parser.is_hidden)
+                    .flat_map(|(child_name, child_parser)| {
+                        std::iter::once(child_name).chain(&child_parser.aliases)
+                    });
+                Err(ParseError::UnknownSubParser {
+                    name: name.to_string(),
+                    suggestion: find_similar_name(name, visible_names),
+                })
+            }
+            1 => Ok(candidates.remove(0)),
+            _ => Err(ParseError::AmbiguousSubParser {
+                name: name.to_string(),
+                candidates,
+            }),
+        }
+    }
+
+    fn find_option(&self, name: &str) -> Result<OptionMatch, ParseError> {
+        if let Some(option_idx) = self.options.iter().position(|option| option.has_name(name)) {
+            return Ok(OptionMatch::Option(option_idx));
+        } else if self.has_version_name(name) {
+            return Ok(OptionMatch::Version);
+        }
+
+        // Hidden options and names can only be given in full
+        let mut candidates: Vec<(OptionMatch, String)> = if self.allow_abbreviations {
+            let version_candidate = match name.strip_prefix("--") {
+                Some(prefix) if !prefix.is_empty() && self.version.is_some() => VERSION_NAMES
+                    .iter()
+                    .find(|version_name| version_name.starts_with(name))
+                    .map(|version_name| (OptionMatch::Version, version_name.to_string())),
+                _ => None,
+            };
+            self.options
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, option)| {
+                    option
+                        .find_name_with_prefix(name)
+                        .map(|candidate| (OptionMatch::Option(idx), candidate))
+                })
+                .chain(version_candidate)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        match candidates.len() {
+            0 => {
+                let version_names = match self.version {
+                    Some(_) => VERSION_NAMES
+                        .map(|version_name| version_name.to_string())
+                        .to_vec(),
+                    None => Vec::new(),
+                };
+                let visible_names: Vec<String> = self
+                    .options
+                    .iter()
+                    .flat_map(|option| option.visible_names())
+                    .chain(version_names)
+                    .collect();
+                Err(ParseError::UnknownOption {
+                    name: name.to_string(),
+                    suggestion: find_similar_name(name, &visible_names),
+                })
+            }
+            1 => Ok(candidates.remove(0).0),
+            _ => Err(ParseError::AmbiguousOption {
+                name: name.to_string(),
+                candidates: candidates
+                    .into_iter()
+                    .map(|(_, candidate)| candidate)
+                    .collect(),
+            }),
+        }
     }
 
-    fn parse_positional(&mut self, result: &mut ParseResult, value: &str) {
+    fn parse_positional(
+        &mut self,
+        result: &mut ParseResult,
+        value: &OsStr,
+        index: usize,
+    ) -> Result<(), ParseError> {
         let positional = match self.positionals.pop_front() {
             Some(positional) => positional,
-            None => todo!("error for exhausted positionals"),
+            None => {
+                return Err(ParseError::UnexpectedPositional {
+                    value: value.to_string_lossy().to_string(),
+                })
+            }
         };
 
-        let parse_value = match ParseValue::from_value(positional.data_type, &value) {
+        if let Some(deprecation) = &positional.deprecation {
+            result.add_warning(deprecation.warning(
+                &*self.messages,
+                ArgumentKind::Positional,
+                &positional.destination,
+            ));
+        }
+
+        let parse_value = match ParseValue::from_os_value(positional.data_type, value) {
             Ok(parse_value) => parse_value,
-            Err(_err) => todo!("error for invalid value for positional"),
+            Err(reason) => {
+                return Err(ParseError::InvalidValue {
+                    destination: positional.destination.clone(),
+                    value: value.to_string_lossy().to_string(),
+                    reason,
+                })
+            }
         };
 
         let add_result = if positional.data_type.is_array() {
@@ -101,136 +697,561 @@ impl Parser {
             result.add_single_value(&positional.destination, parse_value)
         };
 
-        if let Err(_err) = add_result {
-            todo!("error for unable to add parse value")
-        }
+        add_result.map_err(|_| ParseError::DuplicateValue {
+            destination: positional.destination.clone(),
+        })?;
+        result.add_command_line_source(&positional.destination, index);
+        Ok(())
     }
 
-    fn parse_option(&mut self, result: &mut ParseResult, name: &str, raw_args: &mut Skip<Args>) {
-        let mut option_idx = None;
-        for (idx, option) in self.options.iter().enumerate() {
-            if option.has_name(name) {
-                option_idx = Some(idx);
-                break;
+    // The positionals before and after the array get one value each, starting
+    // from the front, and the array gets the values that are left over.
+    fn parse_split_positionals(
+        &mut self,
+        result: &mut ParseResult,
+        positional_args: Vec<(usize, OsString)>,
+        origins: &[Option<response_file::Origin>],
+    ) -> Result<(), ParseError> {
+        let array_idx = self
+            .positionals
+            .iter()
+            .position(|positional| positional.data_type.is_array())
+            .expect("only called when there's an array positional");
+        let other_count = self.positionals.len() - 1;
+        let array_end = array_idx + positional_args.len().saturating_sub(other_count);
+
+        let mut array_positional = None;
+        for (arg_idx, (index, value)) in positional_args.into_iter().enumerate() {
+            if arg_idx == array_end && array_positional.is_none() {
+                // parse_positional keeps the array at the front until it's removed
+                array_positional = self.positionals.pop_front();
             }
+            self.parse_positional(result, &value, index)
+                .map_err(|err| with_origin(err, origins, index))?;
+        }
+        if let Some(array_positional) = array_positional {
+            // Still needed to check whether it's missing or to use its defaults
+            self.positionals.push_front(array_positional);
+        }
+        Ok(())
+    }
+
+    // Whether the argument would be parsed as an option, including
+    // abbreviations and names that are ambiguous between options.
+    fn is_option_name(&self, raw_arg: &OsStr) -> bool {
+        let (name, _) = split_attached_value(raw_arg);
+        matches!(
+            self.find_option(&name),
+            Ok(_) | Err(ParseError::AmbiguousOption { .. })
+        )
+    }
+
+    fn parse_option(
+        &mut self,
+        result: &mut ParseResult,
+        mut option_idx: usize,
+        attached_value: Option<OsString>,
+        index: usize,
+        raw_args: &mut VecDeque<(usize, OsString)>,
+    ) -> Result<(), ParseError> {
+        let option = &self.options[option_idx];
+        if let Some(deprecation) = &option.deprecation {
+            result.add_warning(deprecation.warning(
+                &*self.messages,
+                ArgumentKind::Option,
+                &option.display_name(),
+            ));
+        }
+        if option.is_forwarded {
+            // Only checked by validation in debug builds, but the values
+            // can't silently end up in the wrong destination.
+            option_idx =
+                self.find_replacement(option)
+                    .ok_or_else(|| ParseError::InvalidReplacement {
+                        option: option.display_name(),
+                    })?;
         }
+        let option = &self.options[option_idx];
 
-        let option = if let Some(option_idx) = option_idx {
-            self.options.remove(option_idx)
-        } else {
-            todo!("error for no matching option")
+        let is_flag = matches!(option.data_type, DataType::Bool(false));
+        if attached_value.is_some() && (is_flag || option.arity.is_some()) {
+            return Err(ParseError::UnexpectedValue {
+                option: option.display_name(),
+            });
+        }
+
+        let to_parse_value = |value: &OsStr| {
+            ParseValue::from_os_value(option.data_type, value).map_err(|reason| {
+                ParseError::InvalidValue {
+                    destination: option.display_name(),
+                    value: value.to_string_lossy().to_string(),
+                    reason,
+                }
+            })
         };
 
+        if let Some((min_values, max_values)) = option.arity {
+            let mut group = Vec::new();
+            while group.len() < max_values {
+                // Values that are needed are only cut short by known options,
+                // while the rest are cut short by anything that looks like an option.
+                let is_needed = group.len() < min_values;
+                let is_value = match raw_args.front() {
+                    Some((_, value)) if is_needed => !self.is_option_name(value),
+                    Some((_, value)) => !value.as_encoded_bytes().starts_with(b"-"),
+                    None => false,
+                };
+                if !is_value {
+                    break;
+                }
+                let (_, value) = raw_args.pop_front().expect("was just checked");
+                group.push(to_parse_value(&value)?);
+            }
+
+            if group.len() < min_values {
+                return Err(ParseError::TooFewValues {
+                    option: option.display_name(),
+                    expected: min_values,
+                    found: group.len(),
+                });
+            }
+            result
+                .add_group(&option.destination, group, option.data_type.is_array())
+                .map_err(|_| ParseError::DuplicateValue {
+                    destination: option.destination.clone(),
+                })?;
+            result.add_command_line_source(&option.destination, index);
+            return Ok(());
+        }
+
         // NOTE TO SELF: Boolean arrays need to have specified values
         // since an incremental argument type is to be
         // introduced in the future, making it so allowing them to be
         // unspecified is practically useless.
-        let value = match option.data_type {
-            DataType::Bool(false) => match &option.defaults {
-                Some(defaults) => {
-                    // Validation in OptionArgument ensures there is one default value
-                    match unsafe { defaults.get_unchecked(0).as_str() } {
-                        "true" => "false".to_string(),
-                        "false" => "true".to_string(),
-                        _ => todo!("panic, default is invalid, this is a bug with the validation"),
+        let value = match (attached_value, &option.implicit_value) {
+            (Some(value), _) => value,
+            // Options with an implicit value only take values given with '='
+            (None, Some(implicit_value)) => OsString::from(implicit_value),
+            (None, None) => match option.data_type {
+                DataType::Bool(false) => match &option.defaults {
+                    Some(defaults) => {
+                        // Validation in OptionArgument ensures there is one default value
+                        match unsafe { defaults.get_unchecked(0).as_str() } {
+                            "true" => OsString::from("false"),
+                            "false" => OsString::from("true"),
+                            _ => unreachable!("defaults are validated when they are set"),
+                        }
                     }
-                }
-                None => todo!("panic, theres no defaults, this is a bug with the validation"),
-            },
-            _ => match raw_args.next() {
-                Some(value) => value,
-                None => todo!("error for no value provided"),
+                    None => panic!(
+                        "{}",
+                        DefinitionError::BoolOptionWithoutDefault {
+                            destination: option.destination.clone()
+                        }
+                    ),
+                },
+                _ => match raw_args.pop_front() {
+                    Some((_, value)) => value,
+                    None => {
+                        return Err(ParseError::MissingValue {
+                            option: option.display_name(),
+                        })
+                    }
+                },
             },
         };
 
-        let parse_value = match ParseValue::from_value(option.data_type, &value) {
-            Ok(parse_value) => parse_value,
-            Err(_err) => todo!("error for invalid value for option"),
-        };
+        for value in option.split_value(&value) {
+            if let Some(duplicate_keys) = option.key_values {
+                let (map_key, parse_value) = ParseValue::from_os_pair(option.data_type, &value)
+                    .map_err(|reason| ParseError::InvalidValue {
+                        destination: option.display_name(),
+                        value: value.to_string_lossy().to_string(),
+                        reason,
+                    })?;
+                let is_last_key_winning = duplicate_keys == DuplicateKeys::LastWins;
+                result
+                    .add_map_value(
+                        &option.destination,
+                        map_key,
+                        parse_value,
+                        is_last_key_winning,
+                    )
+                    .map_err(|key| ParseError::DuplicateKey {
+                        option: option.display_name(),
+                        key,
+                    })?;
+                continue;
+            }
+            let parse_value = to_parse_value(&value)?;
+            let add_result = if option.data_type.is_array() {
+                result.add_array_value(&option.destination, parse_value)
+            } else {
+                result.add_single_value(&option.destination, parse_value)
+            };
 
-        let add_result = if option.data_type.is_array() {
-            self.options.push(option.clone());
-            result.add_array_value(&option.destination, parse_value)
-        } else {
-            result.add_single_value(&option.destination, parse_value)
-        };
+            add_result.map_err(|_| ParseError::DuplicateValue {
+                destination: option.destination.clone(),
+            })?;
+        }
+        result.add_command_line_source(&option.destination, index);
+        Ok(())
+    }
+
+    pub fn parse_args(self) -> ParseResult {
+        let raw_args = std::env::args().skip(1); // First arg is always prog name
+        let messages = self.messages.clone();
+        report(&*messages, self.parse_from(raw_args))
+    }
+
+    // Unlike parse_args, arguments that aren't valid UTF-8 don't cause a panic,
+    // and are only rejected for values that need to be valid UTF-8.
+    pub fn parse_args_os(self) -> ParseResult {
+        let raw_args = std::env::args_os().skip(1); // First arg is always prog name
+        let messages = self.messages.clone();
+        report(&*messages, self.parse_from_os(raw_args))
+    }
+
+    pub fn parse_from<I>(self, raw_args: I) -> Result<ParseResult, ParseError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.parse_from_os(raw_args.into_iter().map(OsString::from))
+    }
+
+    pub fn parse_from_os<I>(self, raw_args: I) -> Result<ParseResult, ParseError>
+    where
+        I: IntoIterator<Item = OsString>,
+    {
+        #[cfg(debug_assertions)]
+        if let Err(errors) = self.validate() {
+            let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+            panic!("the parser is invalid:\n{}", errors.join("\n"));
+        }
 
-        if let Err(_err) = add_result {
-            todo!("error for unable to add parse value")
+        match self.response_file_format {
+            Some(format) => {
+                let (raw_args, origins): (Vec<_>, Vec<_>) =
+                    response_file::expand(raw_args, format)?.into_iter().unzip();
+                self.parse_raw_args(&mut raw_args.into_iter().enumerate().collect(), &origins)
+            }
+            None => self.parse_raw_args(&mut raw_args.into_iter().enumerate().collect(), &[]),
         }
     }
 
-    pub fn parse_args(mut self) -> ParseResult {
-        let mut raw_args = std::env::args().skip(1); // First arg is always prog name
+    // The origins are the response file and line of every argument, by index
+    fn parse_raw_args(
+        mut self,
+        raw_args: &mut VecDeque<(usize, OsString)>,
+        origins: &[Option<response_file::Origin>],
+    ) -> Result<ParseResult, ParseError> {
         let mut result = ParseResult::new();
+        // With positionals after an array, the values of the array are only
+        // known once all positional arguments have been seen.
+        let has_split_array = self
+            .positionals
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|positional| positional.data_type.is_array());
+        let mut positional_args = Vec::new();
+        let mut has_verbose_arg = false;
 
-        while let Some(raw_arg) = raw_args.next() {
-            let is_option = raw_arg.starts_with('-');
+        while let Some((index, raw_arg)) = raw_args.pop_front() {
+            let is_option = raw_arg.as_encoded_bytes().starts_with(b"-");
             if is_option {
-                self.parse_option(&mut result, &raw_arg, &mut raw_args);
+                let (name, attached_value) = split_attached_value(&raw_arg);
+                let is_verbose_arg = name == VERBOSE_NAME && attached_value.is_none();
+                has_verbose_arg |= is_verbose_arg;
+                // Options stay registered after being parsed, so that abbreviations
+                // are always resolved against every name of the parser.
+                let option_match = match self.find_option(&name) {
+                    // Unless it's an option of its own, '--verbose' can come before the version
+                    Err(ParseError::UnknownOption { .. })
+                        if is_verbose_arg && self.has_version_arg(raw_args) =>
+                    {
+                        continue;
+                    }
+                    option_match => option_match.map_err(|err| with_origin(err, origins, index))?,
+                };
+                match option_match {
+                    OptionMatch::Option(option_idx) => self
+                        .parse_option(&mut result, option_idx, attached_value, index, raw_args)
+                        .map_err(|err| {
+                            // The error is about the last argument the option consumed
+                            let last_index = raw_args
+                                .front()
+                                .map_or(origins.len(), |(next_index, _)| *next_index);
+                            with_origin(err, origins, last_index.saturating_sub(1))
+                        })?,
+                    OptionMatch::Version if attached_value.is_some() => {
+                        let err = ParseError::UnexpectedValue {
+                            option: VERSION_NAMES[1].to_string(),
+                        };
+                        return Err(with_origin(err, origins, index));
+                    }
+                    OptionMatch::Version => {
+                        let is_verbose = has_verbose_arg
+                            || raw_args.iter().any(|(_, raw_arg)| raw_arg == VERBOSE_NAME);
+                        return Err(self.version_requested(is_verbose));
+                    }
+                }
+            } else if self.positionals.is_empty() && !self.child_parsers.is_empty() {
+                // The rest of the arguments belong to the chosen sub-parser
+                let child_name = self
+                    .find_sub_parser(&raw_arg.to_string_lossy())
+                    .map_err(|err| with_origin(err, origins, index))?;
+                let mut child_parser = self
+                    .child_parsers
+                    .remove(&child_name)
+                    .expect("name was just found");
+                if let Some(deprecation) = &child_parser.deprecation {
+                    result.add_warning(deprecation.warning(
+                        &*self.messages,
+                        ArgumentKind::SubCommand,
+                        &child_name,
+                    ));
+                }
+                child_parser.messages = self.messages.clone();
+                child_parser.prompter = self.prompter.clone();
+                child_parser.allow_abbreviations = self.allow_abbreviations;
+                child_parser.allow_sub_parser_abbreviations = self.allow_sub_parser_abbreviations;
+                // E.g. for 'tool run 1.2.3', whether the version is inherited or not
+                child_parser.program_name =
+                    Some(format!("{} {}", help::program_name(&self), child_name));
+                child_parser
+                    .inherit_global_options(&self.options)
+                    .expect("clashes are rejected when the options are defined");
+                let mut child_result = child_parser.parse_raw_args(raw_args, origins)?;
+                for option in self.options.iter().filter(|option| option.is_global) {
+                    result.move_values(&mut child_result, option)?;
+                }
+                result.set_sub_parser_result(&child_name, child_result);
+                break;
+            } else if has_split_array {
+                positional_args.push((index, raw_arg));
             } else {
-                self.parse_positional(&mut result, &raw_arg);
+                self.parse_positional(&mut result, &raw_arg, index)
+                    .map_err(|err| with_origin(err, origins, index))?;
             }
         }
+        if has_split_array {
+            self.parse_split_positionals(&mut result, positional_args, origins)?;
+        }
 
         for positional in self.positionals {
-            if positional.is_required == Some(true) {
-                todo!("error for missing required positional")
+            if result.has_array(&positional.destination) {
+                // Array arguments still exist in the vectors,
+                // so they need to be skipped if they've already
+                // been parsed.
+                continue;
+            } else if positional.is_required == Some(true) {
+                let answer = match &positional.prompt {
+                    Some(prompt) => prompt::ask_value(
+                        &*self.prompter,
+                        &*self.messages,
+                        prompt,
+                        &positional.destination,
+                        positional.data_type,
+                    )?,
+                    None => None,
+                };
+                let Some(parse_value) = answer else {
+                    return Err(ParseError::MissingPositional {
+                        destination: positional.destination,
+                    });
+                };
+                // Validation ensures only single-valued positionals have a prompt
+                result
+                    .add_single_value(&positional.destination, parse_value)
+                    .map_err(|_| ParseError::DuplicateValue {
+                        destination: positional.destination.clone(),
+                    })?;
+                result.add_source(&positional.destination, ValueSource::Prompt);
             } else if let Some(defaults) = positional.defaults {
-                if result.has_array(&positional.destination) {
-                    // Array arguments still exist in the vectors,
-                    // so they need to be skipped if they've already
-                    // been parsed.
-                    continue;
-                }
                 for default in defaults {
-                    let parse_value = match ParseValue::from_value(positional.data_type, &default) {
-                        Ok(parse_value) => parse_value,
-                        Err(_) => {
-                            todo!("panic, default is invalid, this is a bug with the validation")
-                        }
-                    };
+                    let parse_value = ParseValue::from_value(positional.data_type, &default)
+                        .expect("defaults are validated when they are set");
                     let add_result = if positional.data_type.is_array() {
                         result.add_array_value(&positional.destination, parse_value)
                     } else {
                         result.add_single_value(&positional.destination, parse_value)
                     };
-                    if let Err(_err) = add_result {
-                        todo!("error for unable to add parse value")
-                    }
+                    add_result.map_err(|_| ParseError::DuplicateValue {
+                        destination: positional.destination.clone(),
+                    })?;
                 }
+                result.add_source(&positional.destination, ValueSource::Default);
             }
         }
 
         for option in self.options {
-            if option.is_required == Some(true) {
-                todo!("error for missing required option")
+            if option.is_inherited {
+                // Handled by the parser that defined the global option
+                continue;
+            } else if result.has_value(&option.destination) {
+                continue;
+            } else if option.is_required == Some(true) {
+                let answer = match &option.prompt {
+                    Some(prompt) => prompt::ask_value(
+                        &*self.prompter,
+                        &*self.messages,
+                        prompt,
+                        &option.display_name(),
+                        option.data_type,
+                    )?,
+                    None => None,
+                };
+                let Some(parse_value) = answer else {
+                    return Err(ParseError::MissingOption {
+                        option: option.display_name(),
+                    });
+                };
+                // Validation ensures only single-valued options have a prompt
+                result
+                    .add_single_value(&option.destination, parse_value)
+                    .map_err(|_| ParseError::DuplicateValue {
+                        destination: option.destination.clone(),
+                    })?;
+                result.add_source(&option.destination, ValueSource::Prompt);
             } else if let Some(defaults) = option.defaults {
-                if result.has_array(&option.destination) {
-                    // Array arguments still exist in the vectors,
-                    // so they need to be skipped if they've already
-                    // been parsed.
+                if option.arity.is_some() {
+                    // The defaults of an option with an arity make up a single group
+                    let group = defaults
+                        .iter()
+                        .map(|default| {
+                            ParseValue::from_value(option.data_type, default)
+                                .expect("defaults are validated when they are set")
+                        })
+                        .collect();
+                    result
+                        .add_group(&option.destination, group, option.data_type.is_array())
+                        .map_err(|_| ParseError::DuplicateValue {
+                            destination: option.destination.clone(),
+                        })?;
+                    result.add_source(&option.destination, ValueSource::Default);
                     continue;
                 }
                 for default in defaults {
-                    let parse_value = match ParseValue::from_value(option.data_type, &default) {
-                        Ok(parse_value) => parse_value,
-                        Err(_) => {
-                            todo!("panic, default is invalid, this is a bug with the validation")
-                        }
-                    };
+                    if option.key_values.is_some() {
+                        let (map_key, parse_value) =
+                            ParseValue::from_pair(option.data_type, &default)
+                                .expect("defaults are validated when they are set");
+                        let is_last_key_winning =
+                            option.key_values == Some(DuplicateKeys::LastWins);
+                        result
+                            .add_map_value(
+                                &option.destination,
+                                map_key,
+                                parse_value,
+                                is_last_key_winning,
+                            )
+                            .expect("duplicate keys in defaults are rejected when they are set");
+                        continue;
+                    }
+                    let parse_value = ParseValue::from_value(option.data_type, &default)
+                        .expect("defaults are validated when they are set");
                     let add_result = if option.data_type.is_array() {
                         result.add_array_value(&option.destination, parse_value)
                     } else {
                         result.add_single_value(&option.destination, parse_value)
                     };
-                    if let Err(_err) = add_result {
-                        todo!("error for unable to add parse value")
-                    }
+                    add_result.map_err(|_| ParseError::DuplicateValue {
+                        destination: option.destination.clone(),
+                    })?;
                 }
+                result.add_source(&option.destination, ValueSource::Default);
             }
         }
 
-        result
+        Ok(result)
+    }
+}
+
+// Prints the warnings of parse_args and parse_args_os, or the version or the
+// error, in which case the process exits.
+fn report(messages: &dyn Messages, parsed: Result<ParseResult, ParseError>) -> ParseResult {
+    match parsed {
+        Ok(result) => {
+            for warning in result.warnings() {
+                eprintln!("{}: {}", messages.warning_label(), warning);
+            }
+            result
+        }
+        Err(ParseError::VersionRequested { version }) => {
+            println!("{}", version);
+            std::process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("{}: {}", messages.error_label(), messages.parse_error(&err));
+            std::process::exit(2);
+        }
+    }
+}
+
+// Splits '--name=value' into its name and value. Option names are
+// always valid UTF-8, so names that aren't can't match anyway.
+// Errors about an argument read from a response file say where it was read
+fn with_origin(
+    err: ParseError,
+    origins: &[Option<response_file::Origin>],
+    index: usize,
+) -> ParseError {
+    match origins.get(index) {
+        Some(Some((path, line))) => ParseError::InResponseFile {
+            path: path.clone(),
+            line: *line,
+            error: Box::new(err),
+        },
+        _ => err,
+    }
+}
+
+fn split_attached_value(raw_arg: &OsStr) -> (String, Option<OsString>) {
+    let bytes = raw_arg.as_encoded_bytes();
+    if bytes.starts_with(b"--") {
+        if let Some(equals_idx) = bytes.iter().position(|byte| *byte == b'=') {
+            let name = String::from_utf8_lossy(&bytes[..equals_idx]).to_string();
+            // Splitting right after the ASCII '=' keeps the encoded bytes valid
+            let value = unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[equals_idx + 1..]) };
+            return (name, Some(value.to_os_string()));
+        }
+    }
+    (raw_arg.to_string_lossy().to_string(), None)
+}
+
+// The candidate closest to 'name' by edit distance, if it's close enough
+// to be a likely typo, e.g. '--verbse' for '--verbose'.
+fn find_similar_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a String>,
+) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        // Ties go to the alphabetically first candidate, since the order isn't stable
+        .min_by(|(distance, candidate), (other_distance, other_candidate)| {
+            distance
+                .cmp(other_distance)
+                .then_with(|| candidate.cmp(other_candidate))
+        })
+        .map(|(_, candidate)| candidate.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+    for (a_idx, a_char) in a.chars().enumerate() {
+        let mut prev_diagonal = distances[0];
+        distances[0] = a_idx + 1;
+        for (b_idx, b_char) in b.iter().enumerate() {
+            let substitution = prev_diagonal + usize::from(a_char != *b_char);
+            prev_diagonal = distances[b_idx + 1];
+            distances[b_idx + 1] = substitution
+                .min(distances[b_idx] + 1)
+                .min(distances[b_idx + 1] + 1);
+        }
     }
+    distances[b.len()]
 }
@@ -0,0 +1,321 @@
+use std::fmt::Debug;
+
+use crate::{
+    argument::DataType,
+    error::{ParseError, SyntaxReason, ValueReason},
+};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentKind {
+    Positional,
+    Option,
+    SubCommand,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    Usage,
+    Arguments,
+    Options,
+    Commands,
+    // The sections of man pages that come before the arguments
+    Name,
+    Synopsis,
+}
+
+// The parts of usage lines that stand for any options or sub-command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    Options,
+    Command,
+}
+
+// The columns of the argument tables in markdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Type,
+    Required,
+    Default,
+    Description,
+}
+
+// The user-facing strings of the parser. Every method has an English default,
+// so translations only need to override what they translate, e.g.
+// 'impl Messages for German { fn heading(...) ... }'.
+pub trait Messages: Debug + Send + Sync {
+    // Without the label that parse_args puts in front of it. Translations
+    // that only override some errors can fall back to default_parse_error,
+    // which still uses their other messages.
+    fn parse_error(&self, err: &ParseError) -> String {
+        default_parse_error(self, err)
+    }
+
+    fn value_reason(&self, reason: &ValueReason) -> String {
+        match reason {
+            ValueReason::InvalidUtf8 => "expected valid UTF-8".to_string(),
+            ValueReason::InvalidInt32(err) => format!("expected a 32-bit integer ({})", err),
+            ValueReason::InvalidFloat32(err) => format!("expected a 32-bit float ({})", err),
+            ValueReason::InvalidBool => "expected 'true' or 'false'".to_string(),
+            ValueReason::MissingKey => "expected 'KEY=VALUE'".to_string(),
+            ValueReason::InvalidKeyUtf8 => "expected a key of valid UTF-8".to_string(),
+        }
+    }
+
+    fn syntax_reason(&self, reason: &SyntaxReason) -> String {
+        match reason {
+            SyntaxReason::InvalidUtf8 => "expected valid UTF-8",
+            SyntaxReason::UnterminatedSingleQuote => "unterminated single quote",
+            SyntaxReason::UnterminatedDoubleQuote => "unterminated double quote",
+            SyntaxReason::TrailingBackslash => "trailing backslash",
+        }
+        .to_string()
+    }
+
+    fn did_you_mean(&self, suggestion: &str) -> String {
+        format!("did you mean '{}'?", suggestion)
+    }
+
+    fn error_label(&self) -> String {
+        "error".to_string()
+    }
+
+    fn warning_label(&self) -> String {
+        "warning".to_string()
+    }
+
+    fn deprecated(
+        &self,
+        kind: ArgumentKind,
+        name: &str,
+        replacement: Option<&str>,
+        note: &str,
+    ) -> String {
+        let kind = match kind {
+            ArgumentKind::Positional => "positional",
+            ArgumentKind::Option => "option",
+            ArgumentKind::SubCommand => "sub-command",
+        };
+        match replacement {
+            Some(replacement) => format!(
+                "{} '{}' is deprecated, use '{}' instead: {}",
+                kind, name, replacement, note
+            ),
+            None => format!("{} '{}' is deprecated: {}", kind, name, note),
+        }
+    }
+
+    fn heading(&self, heading: Heading) -> String {
+        match heading {
+            Heading::Usage => "Usage",
+            Heading::Arguments => "Arguments",
+            Heading::Options => "Options",
+            Heading::Commands => "Commands",
+            Heading::Name => "Name",
+            Heading::Synopsis => "Synopsis",
+        }
+        .to_string()
+    }
+
+    fn placeholder(&self, placeholder: Placeholder) -> String {
+        match placeholder {
+            Placeholder::Options => "[OPTIONS]",
+            Placeholder::Command => "[COMMAND]",
+        }
+        .to_string()
+    }
+
+    fn column(&self, column: Column) -> String {
+        match column {
+            Column::Name => "Name",
+            Column::Type => "Type",
+            Column::Required => "Required",
+            Column::Default => "Default",
+            Column::Description => "Description",
+        }
+        .to_string()
+    }
+
+    // The answer in yes-or-no columns, e.g. whether an argument is required
+    fn yes_no(&self, value: bool) -> String {
+        if value { "yes" } else { "no" }.to_string()
+    }
+
+    // The type of a single value, which help output turns into e.g. 'integer[]'
+    fn type_name(&self, data_type: DataType) -> String {
+        match data_type {
+            DataType::Int32(_) => "integer",
+            DataType::Float32(_) => "float",
+            DataType::String(_) => "string",
+            DataType::Bool(_) => "boolean",
+            DataType::Path(_) => "path",
+        }
+        .to_string()
+    }
+
+    fn flag_type_name(&self) -> String {
+        "flag".to_string()
+    }
+
+    fn map_type_name(&self, type_name: &str) -> String {
+        format!("{} map", type_name)
+    }
+
+    // The notes after the help text of arguments in help output
+    fn required_note(&self) -> String {
+        "[required]".to_string()
+    }
+
+    fn default_note(&self, defaults: &[String]) -> String {
+        format!("[default: {}]", defaults.join(", "))
+    }
+
+    fn deprecated_note(&self, replacement: Option<&str>) -> String {
+        match replacement {
+            Some(replacement) => format!("[deprecated, use '{}' instead]", replacement),
+            None => "[deprecated]".to_string(),
+        }
+    }
+
+    // What the prompter shows to ask for a missing argument, e.g. 'Port: '
+    fn prompt(&self, text: &str) -> String {
+        format!("{}: ", text)
+    }
+
+    // Shown by the prompter before asking again for a missing argument
+    fn invalid_answer(&self, reason: &str) -> String {
+        format!("invalid value: {}", reason)
+    }
+
+    fn version_help(&self) -> String {
+        "Print the version and exit".to_string()
+    }
+}
+
+// The English errors, with suggestions and reasons taken from the messages
+pub fn default_parse_error(messages: &(impl Messages + ?Sized), err: &ParseError) -> String {
+    match err {
+        ParseError::UnknownOption { name, suggestion } => {
+            let message = format!("unknown option '{}'", name);
+            match suggestion {
+                Some(suggestion) => format!("{}, {}", message, messages.did_you_mean(suggestion)),
+                None => message,
+            }
+        }
+        ParseError::AmbiguousOption { name, candidates } => format!(
+            "option '{}' is ambiguous, it could be any of: {}",
+            name,
+            quoted_list(candidates)
+        ),
+        ParseError::UnknownSubParser { name, suggestion } => {
+            let message = format!("unknown sub-command '{}'", name);
+            match suggestion {
+                Some(suggestion) => format!("{}, {}", message, messages.did_you_mean(suggestion)),
+                None => message,
+            }
+        }
+        ParseError::AmbiguousSubParser { name, candidates } => format!(
+            "sub-command '{}' is ambiguous, it could be any of: {}",
+            name,
+            quoted_list(candidates)
+        ),
+        ParseError::UnexpectedPositional { value } => {
+            format!("unexpected positional value '{}'", value)
+        }
+        ParseError::MissingValue { option } => format!("option '{}' requires a value", option),
+        ParseError::UnexpectedValue { option } => {
+            format!("option '{}' cannot be given a value with '='", option)
+        }
+        ParseError::TooFewValues {
+            option,
+            expected,
+            found,
+        } => format!(
+            "option '{}' requires at least {} values, but only {} were given",
+            option, expected, found
+        ),
+        ParseError::InvalidValue {
+            destination,
+            value,
+            reason,
+        } => format!(
+            "invalid value '{}' for '{}': {}",
+            value,
+            destination,
+            messages.value_reason(reason)
+        ),
+        ParseError::DuplicateValue { destination } => {
+            format!("a value for '{}' has already been parsed", destination)
+        }
+        ParseError::DuplicateKey { option, key } => {
+            format!(
+                "key '{}' of option '{}' is given more than once",
+                key, option
+            )
+        }
+        ParseError::MissingPositional { destination } => {
+            format!("missing required positional '{}'", destination)
+        }
+        ParseError::MissingOption { option } => format!("missing required option '{}'", option),
+        ParseError::ResponseFileUnreadable {
+            path,
+            origin,
+            reason,
+        } => format!(
+            "{}response file '{}' cannot be read: {}",
+            response_file_origin(origin),
+            path,
+            reason
+        ),
+        ParseError::ResponseFileCycle { path, origin } => format!(
+            "{}response file '{}' includes itself",
+            response_file_origin(origin),
+            path
+        ),
+        ParseError::ResponseFileSyntax { path, line, reason } => format!(
+            "response file '{}', line {}: {}",
+            path,
+            line,
+            messages.syntax_reason(reason)
+        ),
+        ParseError::InvalidReplacement { option } => format!(
+            "option '{}' is forwarded to a replacement that cannot take its values",
+            option
+        ),
+        ParseError::PromptFailed {
+            destination,
+            reason,
+        } => format!("cannot ask for '{}': {}", destination, reason),
+        ParseError::InResponseFile { path, line, error } => format!(
+            "response file '{}', line {}: {}",
+            path,
+            line,
+            messages.parse_error(error)
+        ),
+        ParseError::VersionRequested { version } => version.clone(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct English;
+
+impl Messages for English {}
+
+fn quoted_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("'{}'", item))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Nested response files are reported with the line that references them
+fn response_file_origin(origin: &Option<(String, usize)>) -> String {
+    match origin {
+        Some((path, line)) => format!("response file '{}', line {}: ", path, line),
+        None => String::new(),
+    }
+}
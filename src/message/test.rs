@@ -0,0 +1,209 @@
+use crate::{
+    argument::DataType,
+    error::{ParseError, ValueReason},
+    help::{markdown, terminal},
+    test::args,
+    Parser,
+};
+
+use super::{default_parse_error, ArgumentKind, Column, English, Heading, Messages, Placeholder};
+
+#[derive(Debug)]
+struct German;
+
+impl Messages for German {
+    fn parse_error(&self, err: &ParseError) -> String {
+        match err {
+            ParseError::UnknownOption { name, suggestion } => {
+                let message = format!("unbekannte Option '{}'", name);
+                match suggestion {
+                    Some(suggestion) => format!("{}, {}", message, self.did_you_mean(suggestion)),
+                    None => message,
+                }
+            }
+            _ => default_parse_error(self, err),
+        }
+    }
+
+    fn did_you_mean(&self, suggestion: &str) -> String {
+        format!("meinten Sie '{}'?", suggestion)
+    }
+
+    fn error_label(&self) -> String {
+        "Fehler".to_string()
+    }
+
+    fn deprecated(
+        &self,
+        kind: ArgumentKind,
+        name: &str,
+        _replacement: Option<&str>,
+        note: &str,
+    ) -> String {
+        let kind = match kind {
+            ArgumentKind::Positional => "Argument",
+            ArgumentKind::Option => "Option",
+            ArgumentKind::SubCommand => "Befehl",
+        };
+        format!("{} '{}' ist veraltet: {}", kind, name, note)
+    }
+
+    fn heading(&self, heading: Heading) -> String {
+        match heading {
+            Heading::Usage => "Aufruf",
+            Heading::Arguments => "Argumente",
+            Heading::Options => "Optionen",
+            Heading::Commands => "Befehle",
+            _ => return English.heading(heading),
+        }
+        .to_string()
+    }
+
+    fn value_reason(&self, reason: &ValueReason) -> String {
+        match reason {
+            ValueReason::InvalidInt32(_) => "erwartet eine ganze Zahl".to_string(),
+            _ => English.value_reason(reason),
+        }
+    }
+
+    fn placeholder(&self, placeholder: Placeholder) -> String {
+        match placeholder {
+            Placeholder::Options => "[OPTIONEN]",
+            Placeholder::Command => "[BEFEHL]",
+        }
+        .to_string()
+    }
+
+    fn column(&self, column: Column) -> String {
+        match column {
+            Column::Name => "Name",
+            Column::Type => "Typ",
+            Column::Required => "Erforderlich",
+            Column::Default => "Standard",
+            Column::Description => "Beschreibung",
+        }
+        .to_string()
+    }
+
+    fn yes_no(&self, value: bool) -> String {
+        if value { "ja" } else { "nein" }.to_string()
+    }
+
+    fn type_name(&self, data_type: DataType) -> String {
+        match data_type {
+            DataType::Path(_) => "Pfad".to_string(),
+            _ => English.type_name(data_type),
+        }
+    }
+
+    fn required_note(&self) -> String {
+        "[erforderlich]".to_string()
+    }
+}
+
+fn create_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.program_name("tool").messages(German);
+    parser
+        .option("-o, --output", "output", DataType::Path(false))
+        .is_required(true)
+        .help("Wohin geschrieben wird");
+    parser
+        .option("--quiet", "quiet", DataType::Bool(false))
+        .defaults(&["false"])
+        .deprecated("wird ignoriert", None);
+    parser.sub_parser("run").about("Startet das Programm");
+    parser
+}
+
+#[test]
+fn english_error() {
+    let err = ParseError::UnknownOption {
+        name: "--outptu".to_string(),
+        suggestion: Some("--output".to_string()),
+    };
+    assert_eq!(English.parse_error(&err), err.to_string());
+    assert_eq!(
+        English.parse_error(&err),
+        "unknown option '--outptu', did you mean '--output'?"
+    );
+}
+
+#[test]
+fn translated_error() {
+    let parser = create_parser();
+    let err = parser.parse_from(args(&["--outptu", "out"])).unwrap_err();
+    assert_eq!(
+        German.parse_error(&err),
+        "unbekannte Option '--outptu', meinten Sie '--output'?"
+    );
+}
+
+#[test]
+fn translated_warning() {
+    let parser = create_parser();
+    let result = parser.parse_from(args(&["-o", "out", "--quiet"])).unwrap();
+    assert_eq!(
+        result.warnings(),
+        ["Option '--quiet' ist veraltet: wird ignoriert"]
+    );
+}
+
+#[test]
+fn translated_sub_parser_warning() {
+    let mut parser = Parser::new();
+    parser.messages(German);
+    parser
+        .sub_parser("old")
+        .deprecated("nicht mehr nötig", None);
+    let result = parser.parse_from(args(&["old"])).unwrap();
+    assert_eq!(
+        result.warnings(),
+        ["Befehl 'old' ist veraltet: nicht mehr nötig"]
+    );
+}
+
+#[test]
+fn translated_help() {
+    let parser = create_parser();
+    let help = terminal::render_with(&parser, &[], 80, false).unwrap();
+    assert_eq!(
+        help,
+        "Aufruf: tool [OPTIONEN] [BEFEHL]\n\
+         \n\
+         Optionen:\n  \
+           -o, --output <OUTPUT>  Wohin geschrieben wird [erforderlich]\n      \
+               --quiet            [deprecated]\n\
+         \n\
+         Befehle:\n  \
+           run  Startet das Programm\n"
+    );
+}
+
+#[test]
+fn translated_value_reason() {
+    let mut parser = create_parser();
+    parser.option("-n", "count", DataType::Int32(false));
+    let err = parser
+        .parse_from(args(&["-o", "out", "-n", "x"]))
+        .unwrap_err();
+    assert_eq!(
+        German.parse_error(&err),
+        "invalid value 'x' for '-n': erwartet eine ganze Zahl"
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid value 'x' for '-n': expected a 32-bit integer (invalid digit found in string)"
+    );
+}
+
+#[test]
+fn translated_markdown() {
+    let markdown = markdown::render(&create_parser());
+    assert!(markdown.contains(concat!(
+        "## Optionen\n\n",
+        "| Name | Typ | Erforderlich | Standard | Beschreibung |\n",
+        "| --- | --- | --- | --- | --- |\n",
+        "| `-o`, `--output <OUTPUT>` | Pfad | ja |  | Wohin geschrieben wird |\n",
+    )));
+}
@@ -0,0 +1,94 @@
+use std::{
+    fmt::Debug,
+    io::{BufRead, IsTerminal, Write},
+};
+
+use crate::{
+    argument::{DataType, Prompt},
+    error::ParseError,
+    message::Messages,
+    result::ParseValue,
+};
+
+#[cfg(test)]
+mod test;
+
+// Asks the user for the values of missing required arguments that have a
+// prompt. Replacing the prompter makes prompting testable without a terminal.
+pub trait Prompter: Debug + Send + Sync {
+    // Missing arguments are only asked for when this is true
+    fn is_interactive(&self) -> bool;
+
+    // Shows the prompt, which is already formatted by the messages, e.g. 'Port: ',
+    // and reads the answer, or None at the end of the input
+    fn ask(&self, prompt: &str, is_secret: bool) -> std::io::Result<Option<String>>;
+
+    // Explains why an answer was rejected before the prompt is shown again
+    fn reject(&self, message: &str);
+}
+
+// Prompts on stderr and reads answers from stdin, if stdin is a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn is_interactive(&self) -> bool {
+        std::io::stdin().is_terminal()
+    }
+
+    fn ask(&self, prompt: &str, is_secret: bool) -> std::io::Result<Option<String>> {
+        if is_secret {
+            return match rpassword::prompt_password(prompt) {
+                Ok(answer) => Ok(Some(answer)),
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+                Err(err) => Err(err),
+            };
+        }
+
+        let mut stderr = std::io::stderr();
+        stderr.write_all(prompt.as_bytes())?;
+        stderr.flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        let line_len = answer.trim_end_matches(['\n', '\r']).len();
+        answer.truncate(line_len);
+        Ok(Some(answer))
+    }
+
+    fn reject(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+// Asks for the value of a missing argument until the answer is valid.
+// None means it can't be asked for, so the argument is still missing.
+pub(crate) fn ask_value(
+    prompter: &dyn Prompter,
+    messages: &dyn Messages,
+    prompt: &Prompt,
+    destination: &str,
+    data_type: DataType,
+) -> Result<Option<ParseValue>, ParseError> {
+    if !prompter.is_interactive() {
+        return Ok(None);
+    }
+    loop {
+        let answer = prompter
+            .ask(&messages.prompt(&prompt.text), prompt.is_secret)
+            .map_err(|err| ParseError::PromptFailed {
+                destination: destination.to_string(),
+                reason: err.to_string(),
+            })?;
+        let Some(answer) = answer else {
+            return Ok(None);
+        };
+        match ParseValue::from_value(data_type, &answer) {
+            Ok(parse_value) => return Ok(Some(parse_value)),
+            Err(reason) => {
+                prompter.reject(&messages.invalid_answer(&messages.value_reason(&reason)))
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    argument::DataType,
+    error::{DefinitionError, ParseError},
+    result::{ParseValue, ValueSource},
+    test::args,
+    Parser,
+};
+
+use super::Prompter;
+
+// Answers prompts from a script and records what it was asked
+#[derive(Debug, Clone, Default)]
+struct ScriptedPrompter {
+    is_interactive: bool,
+    answers: Arc<Mutex<VecDeque<String>>>,
+    transcript: Arc<Mutex<Vec<String>>>,
+}
+
+impl ScriptedPrompter {
+    fn new(answers: &[&str]) -> Self {
+        Self {
+            is_interactive: true,
+            answers: Arc::new(Mutex::new(
+                answers.iter().map(|answer| answer.to_string()).collect(),
+            )),
+            transcript: Arc::default(),
+        }
+    }
+
+    fn transcript(&self) -> Vec<String> {
+        self.transcript.lock().unwrap().clone()
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn is_interactive(&self) -> bool {
+        self.is_interactive
+    }
+
+    fn ask(&self, prompt: &str, is_secret: bool) -> std::io::Result<Option<String>> {
+        let kind = if is_secret { "ask secret" } else { "ask" };
+        self.transcript
+            .lock()
+            .unwrap()
+            .push(format!("{}: {}", kind, prompt));
+        Ok(self.answers.lock().unwrap().pop_front())
+    }
+
+    fn reject(&self, message: &str) {
+        self.transcript
+            .lock()
+            .unwrap()
+            .push(format!("reject: {}", message));
+    }
+}
+
+fn create_parser(prompter: &ScriptedPrompter) -> Parser {
+    let mut parser = Parser::new();
+    parser.prompter(prompter.clone());
+    parser
+        .positional("host", DataType::String(false))
+        .is_required(true)
+        .prompt("Host");
+    parser
+        .option("--port", "port", DataType::Int32(false))
+        .is_required(true)
+        .prompt("Port");
+    parser
+        .option("--password", "password", DataType::String(false))
+        .is_required(true)
+        .secret_prompt("Database password");
+    parser
+}
+
+#[test]
+fn missing_arguments_are_asked_for() {
+    let prompter = ScriptedPrompter::new(&["db.local", "5432", "hunter2"]);
+    let result = create_parser(&prompter).parse_from(args(&[])).unwrap();
+    assert_eq!(
+        result.get_single_value("host"),
+        Some(&ParseValue::String("db.local".to_string()))
+    );
+    assert_eq!(
+        result.get_single_value("port"),
+        Some(&ParseValue::Int32(5432))
+    );
+    assert_eq!(
+        result.get_single_value("password"),
+        Some(&ParseValue::String("hunter2".to_string()))
+    );
+    assert_eq!(result.source("port"), Some(ValueSource::Prompt));
+    assert_eq!(
+        prompter.transcript(),
+        [
+            "ask: Host: ",
+            "ask: Port: ",
+            "ask secret: Database password: "
+        ]
+    );
+}
+
+#[test]
+fn given_arguments_are_not_asked_for() {
+    let prompter = ScriptedPrompter::new(&["hunter2"]);
+    let result = create_parser(&prompter)
+        .parse_from(args(&["db.local", "--port", "5432"]))
+        .unwrap();
+    assert_eq!(
+        result.source("host"),
+        Some(ValueSource::CommandLine {
+            index: 0,
+            occurrence: 1
+        })
+    );
+    assert_eq!(prompter.transcript(), ["ask secret: Database password: "]);
+}
+
+#[test]
+fn invalid_answers_are_asked_for_again() {
+    let prompter = ScriptedPrompter::new(&["many", "5432", "hunter2"]);
+    let result = create_parser(&prompter)
+        .parse_from(args(&["db.local"]))
+        .unwrap();
+    assert_eq!(
+        result.get_single_value("port"),
+        Some(&ParseValue::Int32(5432))
+    );
+    assert_eq!(
+        prompter.transcript(),
+        [
+            "ask: Port: ",
+            "reject: invalid value: expected a 32-bit integer (invalid digit found in string)",
+            "ask: Port: ",
+            "ask secret: Database password: "
+        ]
+    );
+}
+
+#[test]
+fn end_of_input_is_a_missing_argument() {
+    let prompter = ScriptedPrompter::new(&["db.local"]);
+    let result = create_parser(&prompter).parse_from(args(&[]));
+    assert_eq!(
+        result.unwrap_err(),
+        ParseError::MissingOption {
+            option: "--port".to_string()
+        }
+    );
+}
+
+#[test]
+fn not_interactive() {
+    let prompter = ScriptedPrompter {
+        is_interactive: false,
+        ..ScriptedPrompter::new(&["db.local"])
+    };
+    let result = create_parser(&prompter).parse_from(args(&[]));
+    assert_eq!(
+        result.unwrap_err(),
+        ParseError::MissingPositional {
+            destination: "host".to_string()
+        }
+    );
+    assert!(prompter.transcript().is_empty());
+}
+
+#[test]
+fn sub_parsers_use_the_prompter_of_their_parent() {
+    let prompter = ScriptedPrompter::new(&["alice"]);
+    let mut parser = Parser::new();
+    parser.prompter(prompter.clone());
+    parser
+        .sub_parser("login")
+        .option("--user", "user", DataType::String(false))
+        .is_required(true)
+        .prompt("User");
+    let result = parser.parse_from(args(&["login"])).unwrap();
+    let login_result = result.sub_parser_result().unwrap();
+    assert_eq!(
+        login_result.get_single_value("user"),
+        Some(&ParseValue::String("alice".to_string()))
+    );
+}
+
+#[test]
+fn unpromptable_arguments() {
+    let mut parser = Parser::new();
+    parser
+        .positional("files", DataType::Path(true))
+        .is_required(true)
+        .prompt("Files");
+    parser
+        .option("--name", "name", DataType::String(false))
+        .prompt("Name");
+    assert_eq!(
+        parser.validate().unwrap_err(),
+        [
+            DefinitionError::UnpromptableArgument {
+                destination: "name".to_string()
+            },
+            DefinitionError::UnpromptableArgument {
+                destination: "files".to_string()
+            },
+        ]
+    );
+}
@@ -0,0 +1,207 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::{ParseError, SyntaxReason};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseFileFormat {
+    // Every non-empty line is one argument, taken as is. On Unix, lines don't
+    // need to be valid UTF-8, like command-line arguments.
+    Lines,
+    // Arguments are separated by whitespace and can be quoted like in a POSIX shell
+    ShellQuoted,
+}
+
+// The response file and line an argument was read from
+pub(crate) type Origin = (String, usize);
+
+// Replaces every '@path' argument with the arguments read from the file at 'path',
+// which can in turn contain '@path' arguments of their own. Every argument comes
+// with the response file and line it was read from, if any.
+pub(crate) fn expand(
+    raw_args: impl IntoIterator<Item = OsString>,
+    format: ResponseFileFormat,
+) -> Result<Vec<(OsString, Option<Origin>)>, ParseError> {
+    let mut expanded_args = Vec::new();
+    let mut file_stack = Vec::new();
+    for raw_arg in raw_args {
+        expand_arg(raw_arg, None, format, &mut file_stack, &mut expanded_args)?;
+    }
+    Ok(expanded_args)
+}
+
+// The origin is the response file and line the argument was read from
+fn expand_arg(
+    raw_arg: OsString,
+    origin: Option<(&Path, usize)>,
+    format: ResponseFileFormat,
+    file_stack: &mut Vec<PathBuf>,
+    expanded_args: &mut Vec<(OsString, Option<Origin>)>,
+) -> Result<(), ParseError> {
+    let owned_origin = || origin.map(|(path, line)| (path.display().to_string(), line));
+    let path = match raw_arg.as_encoded_bytes() {
+        [b'@', rest @ ..] if !rest.is_empty() => {
+            // Removing the ASCII '@' keeps the rest of the encoded bytes valid
+            Path::new(unsafe { OsStr::from_encoded_bytes_unchecked(rest) })
+        }
+        _ => {
+            expanded_args.push((raw_arg, owned_origin()));
+            return Ok(());
+        }
+    };
+
+    let canonical_path =
+        fs::canonicalize(path).map_err(|err| ParseError::ResponseFileUnreadable {
+            path: path.display().to_string(),
+            origin: owned_origin(),
+            reason: err.to_string(),
+        })?;
+    if file_stack.contains(&canonical_path) {
+        return Err(ParseError::ResponseFileCycle {
+            path: path.display().to_string(),
+            origin: owned_origin(),
+        });
+    }
+    let contents = fs::read(&canonical_path).map_err(|err| ParseError::ResponseFileUnreadable {
+        path: path.display().to_string(),
+        origin: owned_origin(),
+        reason: err.to_string(),
+    })?;
+
+    let file_args = match format {
+        ResponseFileFormat::Lines => split_lines(&contents),
+        ResponseFileFormat::ShellQuoted => split_shell_words(&contents),
+    }
+    .map_err(|(line, reason)| ParseError::ResponseFileSyntax {
+        path: path.display().to_string(),
+        line,
+        reason,
+    })?;
+
+    file_stack.push(canonical_path);
+    for (file_arg, line) in file_args {
+        expand_arg(
+            file_arg,
+            Some((path, line)),
+            format,
+            file_stack,
+            expanded_args,
+        )?;
+    }
+    file_stack.pop();
+    Ok(())
+}
+
+// Both splitting functions return the arguments with the line they start on
+fn split_lines(contents: &[u8]) -> Result<Vec<(OsString, usize)>, (usize, SyntaxReason)> {
+    let mut args = Vec::new();
+    for (idx, line) in contents.split(|byte| *byte == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if !line.is_empty() {
+            let arg =
+                os_string_from_bytes(line).ok_or_else(|| (idx + 1, SyntaxReason::InvalidUtf8))?;
+            args.push((arg, idx + 1));
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: &[u8]) -> Option<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Some(OsString::from_vec(bytes.to_vec()))
+}
+
+// Elsewhere, arbitrary bytes can't be turned into an OsString
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: &[u8]) -> Option<OsString> {
+    std::str::from_utf8(bytes).ok().map(OsString::from)
+}
+
+fn split_shell_words(contents: &[u8]) -> Result<Vec<(OsString, usize)>, (usize, SyntaxReason)> {
+    let mut line = 1;
+    let contents = std::str::from_utf8(contents).map_err(|err| {
+        let valid_contents = &contents[..err.valid_up_to()];
+        let line = 1 + valid_contents.iter().filter(|byte| **byte == b'\n').count();
+        (line, SyntaxReason::InvalidUtf8)
+    })?;
+
+    let mut args = Vec::new();
+    let mut chars = contents.chars().peekable();
+    // The word and the line it started on
+    let mut word: Option<(String, usize)> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | ' ' | '\t' | '\r' => {
+                if let Some((word, start_line)) = word.take() {
+                    args.push((OsString::from(word), start_line));
+                }
+                if c == '\n' {
+                    line += 1;
+                }
+            }
+            '#' if word.is_none() => {
+                // Comments last until the end of the line
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '\'' => {
+                let (word, start_line) = word.get_or_insert_with(|| (String::new(), line));
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            word.push(c);
+                        }
+                        None => return Err((*start_line, SyntaxReason::UnterminatedSingleQuote)),
+                    }
+                }
+            }
+            '"' => {
+                let (word, start_line) = word.get_or_insert_with(|| (String::new(), line));
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => {
+                                return Err((*start_line, SyntaxReason::UnterminatedDoubleQuote))
+                            }
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            word.push(c);
+                        }
+                        None => return Err((*start_line, SyntaxReason::UnterminatedDoubleQuote)),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => line += 1,
+                Some(c) => word.get_or_insert_with(|| (String::new(), line)).0.push(c),
+                None => return Err((line, SyntaxReason::TrailingBackslash)),
+            },
+            c => word.get_or_insert_with(|| (String::new(), line)).0.push(c),
+        }
+    }
+    if let Some((word, start_line)) = word {
+        args.push((OsString::from(word), start_line));
+    }
+    Ok(args)
+}
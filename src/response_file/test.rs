@@ -0,0 +1,230 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    argument::DataType,
+    error::{ParseError, SyntaxReason, ValueReason},
+    test::args,
+    Parser,
+};
+
+use super::ResponseFileFormat;
+
+fn response_file_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("argument_parser_{}_{}", std::process::id(), name))
+}
+
+// Deletes the file again when it goes out of scope, also when a test fails
+struct ResponseFile {
+    path: PathBuf,
+}
+
+impl ResponseFile {
+    fn new(name: &str, contents: impl AsRef<[u8]>) -> Self {
+        let path = response_file_path(name);
+        fs::write(&path, contents).unwrap();
+        Self { path }
+    }
+
+    fn arg(&self) -> String {
+        format!("@{}", self.path.display())
+    }
+}
+
+impl Drop for ResponseFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn create_parser(format: ResponseFileFormat) -> Parser {
+    let mut parser = Parser::new();
+    parser.response_files(Some(format));
+    parser.positional("files", DataType::String(true));
+    parser.option("-o", "output", DataType::String(false));
+    parser
+}
+
+mod valid {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let file = ResponseFile::new("lines.rsp", "-o\nout file.txt\r\n\na.txt\n");
+        let parser = create_parser(ResponseFileFormat::Lines);
+        let result = parser.parse_from(args(&[&file.arg(), "b.txt"])).unwrap();
+        assert_eq!(
+            result.get_single::<String>("output"),
+            Some("out file.txt".to_string())
+        );
+        assert_eq!(
+            result.get_array::<String>("files"),
+            Some(vec!["a.txt".to_string(), "b.txt".to_string()])
+        );
+    }
+
+    #[test]
+    fn shell_quoted() {
+        let file = ResponseFile::new(
+            "shell_quoted.rsp",
+            "# a comment\n-o 'out file.txt' \"a \\\"quoted\\\" name\"\nb\\ c d'e'\n",
+        );
+        let parser = create_parser(ResponseFileFormat::ShellQuoted);
+        let result = parser.parse_from(args(&[&file.arg()])).unwrap();
+        assert_eq!(
+            result.get_single::<String>("output"),
+            Some("out file.txt".to_string())
+        );
+        assert_eq!(
+            result.get_array::<String>("files"),
+            Some(vec![
+                "a \"quoted\" name".to_string(),
+                "b c".to_string(),
+                "de".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let inner_file = ResponseFile::new("inner.rsp", "c\n");
+        let outer_file = ResponseFile::new("outer.rsp", format!("b\n{}\n", inner_file.arg()));
+        let parser = create_parser(ResponseFileFormat::Lines);
+        let result = parser
+            .parse_from(args(&["a", &outer_file.arg(), &inner_file.arg()]))
+            .unwrap();
+        assert_eq!(
+            result.get_array::<String>("files"),
+            Some(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string(),
+                "c".to_string()
+            ])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_lines() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let file = ResponseFile::new("non_utf8.rsp", b"a\xff\nb\n");
+        let mut parser = Parser::new();
+        parser.response_files(Some(ResponseFileFormat::Lines));
+        parser.positional("paths", DataType::Path(true));
+        let result = parser.parse_from(args(&[&file.arg()])).unwrap();
+        assert_eq!(
+            result.get_array::<PathBuf>("paths"),
+            Some(vec![
+                PathBuf::from(OsStr::from_bytes(b"a\xff")),
+                PathBuf::from("b")
+            ])
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let mut parser = create_parser(ResponseFileFormat::Lines);
+        parser.response_files(None);
+        let result = parser.parse_from(args(&["@missing.rsp", "@"])).unwrap();
+        assert_eq!(
+            result.get_array::<String>("files"),
+            Some(vec!["@missing.rsp".to_string(), "@".to_string()])
+        );
+    }
+}
+
+mod invalid {
+    use super::*;
+
+    #[test]
+    fn cycle() {
+        let b_path = response_file_path("cycle_b.rsp");
+        let a_file = ResponseFile::new("cycle_a.rsp", format!("a\n@{}\n", b_path.display()));
+        let b_file = ResponseFile::new("cycle_b.rsp", format!("b\n{}\n", a_file.arg()));
+        let parser = create_parser(ResponseFileFormat::Lines);
+        let result = parser.parse_from(args(&[&b_file.arg()]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::ResponseFileCycle {
+                path: b_path.display().to_string(),
+                origin: Some((a_file.path.display().to_string(), 2)),
+            }
+        );
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let file = ResponseFile::new("unterminated.rsp", "a\nb 'c\nd\n");
+        let parser = create_parser(ResponseFileFormat::ShellQuoted);
+        let result = parser.parse_from(args(&[&file.arg()]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::ResponseFileSyntax {
+                path: file.path.display().to_string(),
+                line: 2,
+                reason: SyntaxReason::UnterminatedSingleQuote,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_file() {
+        let parser = create_parser(ResponseFileFormat::Lines);
+        let result = parser.clone().parse_from(args(&["@does/not/exist.rsp"]));
+        assert!(matches!(
+            result,
+            Err(ParseError::ResponseFileUnreadable { origin: None, .. })
+        ));
+
+        let file = ResponseFile::new("missing_nested.rsp", "a\n\n@does/not/exist.rsp\n");
+        let result = parser.parse_from(args(&[&file.arg()]));
+        assert!(matches!(
+            result,
+            Err(ParseError::ResponseFileUnreadable { origin: Some((path, 3)), .. })
+                if path == file.path.display().to_string()
+        ));
+    }
+
+    #[test]
+    fn invalid_value() {
+        let file = ResponseFile::new("invalid_value.rsp", "a\n\n-n\nx\n");
+        let mut parser = create_parser(ResponseFileFormat::Lines);
+        parser.option("-n", "count", DataType::Int32(false));
+        let result = parser.parse_from(args(&[&file.arg()]));
+        let err = result.unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::InResponseFile {
+                path: file.path.display().to_string(),
+                line: 4,
+                error: Box::new(ParseError::InvalidValue {
+                    destination: "-n".to_string(),
+                    value: "x".to_string(),
+                    reason: ValueReason::InvalidInt32("x".parse::<i32>().unwrap_err()),
+                }),
+            }
+        );
+        assert!(err.to_string().starts_with(&format!(
+            "response file '{}', line 4: ",
+            file.path.display()
+        )));
+    }
+
+    #[test]
+    fn unknown_option() {
+        let file = ResponseFile::new("unknown_option.rsp", "a\n--bogus\n");
+        let parser = create_parser(ResponseFileFormat::Lines);
+        let result = parser.clone().parse_from(args(&[&file.arg()]));
+        assert!(matches!(
+            result,
+            Err(ParseError::InResponseFile { line: 2, error, .. })
+                if matches!(*error, ParseError::UnknownOption { .. })
+        ));
+
+        // Arguments given directly are reported as before
+        let valid_file = ResponseFile::new("valid_before_unknown.rsp", "a\n");
+        let result = parser.parse_from(args(&[&valid_file.arg(), "--other"]));
+        assert!(matches!(result, Err(ParseError::UnknownOption { .. })));
+    }
+}
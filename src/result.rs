@@ -1,9 +1,25 @@
-use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
-use crate::argument::DataType;
+use crate::{
+    argument::{
+        option::{DuplicateKeys, OptionArgument},
+        DataType,
+    },
+    error::{ParseError, ValueReason},
+};
 
-#[derive(Debug)]
-pub(crate) enum ParseValue {
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseValue {
     Int32(i32),
     Float32(f32),
     String(String),
@@ -12,34 +28,143 @@ pub(crate) enum ParseValue {
 }
 
 impl ParseValue {
-    pub(crate) fn from_value(data_type: DataType, value: &str) -> Result<Self, String> {
+    // Paths are kept as they are, while every other type needs valid UTF-8.
+    pub(crate) fn from_os_value(data_type: DataType, value: &OsStr) -> Result<Self, ValueReason> {
+        match (data_type, value.to_str()) {
+            (DataType::Path(_), _) => Ok(ParseValue::Path(PathBuf::from(value))),
+            (_, Some(value)) => Self::from_value(data_type, value),
+            (_, None) => Err(ValueReason::InvalidUtf8),
+        }
+    }
+
+    pub(crate) fn from_value(data_type: DataType, value: &str) -> Result<Self, ValueReason> {
         // TODO: paths could need more validation
         match data_type {
             DataType::Int32(_) => match value.parse::<i32>() {
                 Ok(value) => Ok(ParseValue::Int32(value)),
-                Err(_) => todo!("i32 parse error message"),
+                Err(err) => Err(ValueReason::InvalidInt32(err)),
             },
             DataType::Float32(_) => match value.parse::<f32>() {
                 Ok(value) => Ok(ParseValue::Float32(value)),
-                Err(_) => todo!("f32 parse error message"),
+                Err(err) => Err(ValueReason::InvalidFloat32(err)),
             },
             DataType::String(_) => Ok(ParseValue::String(value.to_string())),
             DataType::Bool(_) => match value.parse::<bool>() {
                 Ok(value) => Ok(ParseValue::Bool(value)),
-                Err(_) => todo!("bool parse error message"),
-            },
-            DataType::Path(_) => match PathBuf::from_str(value) {
-                Ok(value) => Ok(ParseValue::Path(value)),
-                Err(_) => todo!("path parse error message"),
+                Err(_) => Err(ValueReason::InvalidBool),
             },
+            DataType::Path(_) => Ok(ParseValue::Path(PathBuf::from(value))),
+        }
+    }
+
+    // Parses 'KEY=VALUE' into its key and a value of the data type.
+    // The key needs to be valid UTF-8, even if the value is a path.
+    pub(crate) fn from_os_pair(
+        data_type: DataType,
+        pair: &OsStr,
+    ) -> Result<(String, Self), ValueReason> {
+        let bytes = pair.as_encoded_bytes();
+        let equals_idx = match bytes.iter().position(|byte| *byte == b'=') {
+            Some(equals_idx) if equals_idx > 0 => equals_idx,
+            _ => return Err(ValueReason::MissingKey),
+        };
+        let key =
+            std::str::from_utf8(&bytes[..equals_idx]).map_err(|_| ValueReason::InvalidKeyUtf8)?;
+        // Splitting right after the ASCII '=' keeps the encoded bytes valid
+        let value = unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[equals_idx + 1..]) };
+        Ok((key.to_string(), Self::from_os_value(data_type, value)?))
+    }
+
+    pub(crate) fn from_pair(
+        data_type: DataType,
+        pair: &str,
+    ) -> Result<(String, Self), ValueReason> {
+        Self::from_os_pair(data_type, OsStr::new(pair))
+    }
+
+    // The type of a single value, so it's never an array
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ParseValue::Int32(_) => DataType::Int32(false),
+            ParseValue::Float32(_) => DataType::Float32(false),
+            ParseValue::String(_) => DataType::String(false),
+            ParseValue::Bool(_) => DataType::Bool(false),
+            ParseValue::Path(_) => DataType::Path(false),
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            ParseValue::Int32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            ParseValue::Float32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ParseValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ParseValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_path(&self) -> Option<&Path> {
+        match self {
+            ParseValue::Path(value) => Some(value),
+            _ => None,
         }
     }
 }
 
+impl Display for ParseValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseValue::Int32(value) => write!(f, "{}", value),
+            ParseValue::Float32(value) => write!(f, "{}", value),
+            ParseValue::String(value) => write!(f, "{}", value),
+            ParseValue::Bool(value) => write!(f, "{}", value),
+            ParseValue::Path(value) => write!(f, "{}", value.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueSource {
+    // 'index' is the position of the argument among the parsed arguments,
+    // after response files have been expanded. For parse_args, 0 is the first
+    // argument after the program name. 'occurrence' counts from 1, and refers
+    // to the last time the destination was given a value.
+    CommandLine { index: usize, occurrence: usize },
+    Default,
+    // Asked for interactively, since the argument was missing
+    Prompt,
+}
+
 #[derive(Debug)]
 pub struct ParseResult {
     single_values: HashMap<String, ParseValue>,
     array_values: HashMap<String, Vec<ParseValue>>,
+    // Values of arguments with an arity, grouped by occurrence
+    grouped_values: HashMap<String, Vec<Vec<ParseValue>>>,
+    // Values of key-value arguments, in the order the keys were first given
+    map_values: HashMap<String, Vec<(String, ParseValue)>>,
+    sources: HashMap<String, ValueSource>,
+    // Warnings about the use of deprecated arguments, including the sub-parser's
+    warnings: Vec<String>,
+    sub_parser_result: Option<(String, Box<ParseResult>)>,
 }
 
 impl ParseResult {
@@ -47,6 +172,11 @@ impl ParseResult {
         Self {
             single_values: HashMap::new(),
             array_values: HashMap::new(),
+            grouped_values: HashMap::new(),
+            map_values: HashMap::new(),
+            sources: HashMap::new(),
+            warnings: Vec::new(),
+            sub_parser_result: None,
         }
     }
 
@@ -58,6 +188,210 @@ impl ParseResult {
         self.array_values.contains_key(key)
     }
 
+    pub fn has_groups(&self, key: &str) -> bool {
+        self.grouped_values.contains_key(key)
+    }
+
+    pub fn has_map(&self, key: &str) -> bool {
+        self.map_values.contains_key(key)
+    }
+
+    pub(crate) fn has_value(&self, key: &str) -> bool {
+        self.has_single(key) || self.has_array(key) || self.has_groups(key) || self.has_map(key)
+    }
+
+    // The number of destinations with values, not counting the sub-parser's
+    pub fn len(&self) -> usize {
+        self.single_values.len()
+            + self.array_values.len()
+            + self.grouped_values.len()
+            + self.map_values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // All destinations with values in sorted order, not counting the sub-parser's
+    pub fn destinations(&self) -> impl Iterator<Item = &str> {
+        let mut destinations: Vec<&str> = self
+            .single_values
+            .keys()
+            .chain(self.array_values.keys())
+            .chain(self.grouped_values.keys())
+            .chain(self.map_values.keys())
+            .map(|key| key.as_str())
+            .collect();
+        destinations.sort();
+        destinations.into_iter()
+    }
+
+    pub fn single_values(&self) -> impl Iterator<Item = (&str, &ParseValue)> {
+        let mut single_values: Vec<(&str, &ParseValue)> = self
+            .single_values
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+            .collect();
+        single_values.sort_by_key(|(key, _)| *key);
+        single_values.into_iter()
+    }
+
+    pub fn array_values(&self) -> impl Iterator<Item = (&str, &[ParseValue])> {
+        let mut array_values: Vec<(&str, &[ParseValue])> = self
+            .array_values
+            .iter()
+            .map(|(key, array)| (key.as_str(), array.as_slice()))
+            .collect();
+        array_values.sort_by_key(|(key, _)| *key);
+        array_values.into_iter()
+    }
+
+    pub fn source(&self, key: &str) -> Option<ValueSource> {
+        self.sources.get(key).copied()
+    }
+
+    pub(crate) fn add_source(&mut self, key: &str, source: ValueSource) {
+        self.sources.insert(key.to_string(), source);
+    }
+
+    pub(crate) fn add_command_line_source(&mut self, key: &str, index: usize) {
+        let occurrence = match self.sources.get(key) {
+            Some(ValueSource::CommandLine { occurrence, .. }) => occurrence + 1,
+            _ => 1,
+        };
+        self.add_source(key, ValueSource::CommandLine { index, occurrence });
+    }
+
+    pub fn grouped_values(&self) -> impl Iterator<Item = (&str, &[Vec<ParseValue>])> {
+        let mut grouped_values: Vec<(&str, &[Vec<ParseValue>])> = self
+            .grouped_values
+            .iter()
+            .map(|(key, groups)| (key.as_str(), groups.as_slice()))
+            .collect();
+        grouped_values.sort_by_key(|(key, _)| *key);
+        grouped_values.into_iter()
+    }
+
+    pub fn map_values(&self) -> impl Iterator<Item = (&str, &[(String, ParseValue)])> {
+        let mut map_values: Vec<(&str, &[(String, ParseValue)])> = self
+            .map_values
+            .iter()
+            .map(|(key, pairs)| (key.as_str(), pairs.as_slice()))
+            .collect();
+        map_values.sort_by_key(|(key, _)| *key);
+        map_values.into_iter()
+    }
+
+    pub fn get_single_value(&self, key: &str) -> Option<&ParseValue> {
+        self.single_values.get(key)
+    }
+
+    pub fn get_array_values(&self, key: &str) -> Option<&[ParseValue]> {
+        self.array_values.get(key).map(|array| array.as_slice())
+    }
+
+    pub fn get_grouped_values(&self, key: &str) -> Option<&[Vec<ParseValue>]> {
+        self.grouped_values.get(key).map(|groups| groups.as_slice())
+    }
+
+    pub fn get_map_values(&self, key: &str) -> Option<&[(String, ParseValue)]> {
+        self.map_values.get(key).map(|pairs| pairs.as_slice())
+    }
+
+    pub fn get_map_value(&self, key: &str, map_key: &str) -> Option<&ParseValue> {
+        self.map_values
+            .get(key)?
+            .iter()
+            .find(|(pair_key, _)| pair_key == map_key)
+            .map(|(_, parse_value)| parse_value)
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub(crate) fn add_warning(&mut self, warning: String) {
+        // Arguments that are used several times only warn once
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    pub fn sub_parser_name(&self) -> Option<&str> {
+        self.sub_parser_result
+            .as_ref()
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn sub_parser_result(&self) -> Option<&ParseResult> {
+        self.sub_parser_result
+            .as_ref()
+            .map(|(_, result)| result.as_ref())
+    }
+
+    pub(crate) fn set_sub_parser_result(&mut self, name: &str, mut result: ParseResult) {
+        for warning in std::mem::take(&mut result.warnings) {
+            self.add_warning(warning);
+        }
+        self.sub_parser_result = Some((name.to_string(), Box::new(result)));
+    }
+
+    // Moves the values of a global option from the result of the sub-parser
+    // that parsed them, checking them against the values parsed here.
+    pub(crate) fn move_values(
+        &mut self,
+        other: &mut ParseResult,
+        option: &OptionArgument,
+    ) -> Result<(), ParseError> {
+        let key = option.destination.as_str();
+        let duplicate_value = |_| ParseError::DuplicateValue {
+            destination: option.destination.clone(),
+        };
+        if let Some(parse_value) = other.single_values.remove(key) {
+            self.add_single_value(key, parse_value)
+                .map_err(duplicate_value)?;
+        }
+        if let Some(array) = other.array_values.remove(key) {
+            for parse_value in array {
+                self.add_array_value(key, parse_value)
+                    .map_err(duplicate_value)?;
+            }
+        }
+        if let Some(groups) = other.grouped_values.remove(key) {
+            for group in groups {
+                self.add_group(key, group, option.data_type.is_array())
+                    .map_err(duplicate_value)?;
+            }
+        }
+        if let Some(pairs) = other.map_values.remove(key) {
+            let is_last_key_winning = option.key_values == Some(DuplicateKeys::LastWins);
+            for (map_key, parse_value) in pairs {
+                self.add_map_value(key, map_key, parse_value, is_last_key_winning)
+                    .map_err(|map_key| ParseError::DuplicateKey {
+                        option: option.display_name(),
+                        key: map_key,
+                    })?;
+            }
+        }
+        let source = match (self.sources.get(key), other.sources.remove(key)) {
+            (
+                Some(ValueSource::CommandLine { occurrence, .. }),
+                Some(ValueSource::CommandLine {
+                    index,
+                    occurrence: other_occurrence,
+                }),
+            ) => Some(ValueSource::CommandLine {
+                index,
+                occurrence: occurrence + other_occurrence,
+            }),
+            (_, other_source) => other_source,
+        };
+        if let Some(source) = source {
+            self.add_source(key, source);
+        }
+        Ok(())
+    }
+
     pub(crate) fn add_single_value(
         &mut self,
         key: &str,
@@ -88,6 +422,48 @@ impl ParseResult {
         }
     }
 
+    pub(crate) fn add_group(
+        &mut self,
+        key: &str,
+        group: Vec<ParseValue>,
+        is_array: bool,
+    ) -> Result<(), String> {
+        match self.grouped_values.get_mut(key) {
+            Some(groups) if is_array => {
+                groups.push(group);
+                Ok(())
+            }
+            Some(_) => Err(format!("a value for '{}' has already been parsed", key)),
+            None => {
+                self.grouped_values.insert(key.to_string(), vec![group]);
+                Ok(())
+            }
+        }
+    }
+
+    // Either replaces the value of a duplicate key in place, or fails
+    // with the duplicate key, depending on 'is_last_key_winning'.
+    pub(crate) fn add_map_value(
+        &mut self,
+        key: &str,
+        map_key: String,
+        parse_value: ParseValue,
+        is_last_key_winning: bool,
+    ) -> Result<(), String> {
+        let pairs = self.map_values.entry(key.to_string()).or_default();
+        match pairs.iter_mut().find(|(pair_key, _)| *pair_key == map_key) {
+            Some((_, value)) if is_last_key_winning => {
+                *value = parse_value;
+                Ok(())
+            }
+            Some(_) => Err(map_key),
+            None => {
+                pairs.push((map_key, parse_value));
+                Ok(())
+            }
+        }
+    }
+
     pub fn get_single<T: Clone>(&self, key: &str) -> Option<T> {
         if !self.single_values.contains_key(key) {
             None
@@ -104,6 +480,9 @@ impl ParseResult {
         }
     }
 
+    /// # Safety
+    ///
+    /// `key` must have a single value, and `T` must be the type the value was parsed as.
     pub unsafe fn get_single_unchecked<T: Clone>(&self, key: &str) -> T {
         let generic_ptr = match &self.single_values[key] {
             ParseValue::Int32(value) => value as *const i32 as *const T,
@@ -115,6 +494,9 @@ impl ParseResult {
         (*generic_ptr).clone()
     }
 
+    /// # Safety
+    ///
+    /// `key` must have an array value, and `T` must be the type the values were parsed as.
     pub unsafe fn get_array_unchecked<T: Clone>(&self, key: &str) -> Vec<T> {
         let mut array = Vec::new();
         for parse_value in &self.array_values[key] {
@@ -0,0 +1,87 @@
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use super::{ParseResult, ParseValue};
+
+impl Serialize for ParseValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ParseValue::Int32(value) => serializer.serialize_i32(*value),
+            ParseValue::Float32(value) => serializer.serialize_f32(*value),
+            ParseValue::String(value) => serializer.serialize_str(value),
+            ParseValue::Bool(value) => serializer.serialize_bool(*value),
+            // Paths that aren't valid UTF-8 can't be represented as strings otherwise
+            ParseValue::Path(value) => serializer.serialize_str(&value.to_string_lossy()),
+        }
+    }
+}
+
+// Serialized as '{"values": {...}, "sub_parser": null}', where the values are
+// ordered by destination, and the sub-parser is '{"name": ..., "values": ...,
+// "sub_parser": ...}' if one was chosen.
+impl Serialize for ParseResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("values", &SortedValues(self))?;
+        map.serialize_entry(
+            "sub_parser",
+            &self
+                .sub_parser_result
+                .as_ref()
+                .map(|(name, result)| NamedResult(name, result)),
+        )?;
+        map.end()
+    }
+}
+
+struct SortedValues<'a>(&'a ParseResult);
+
+impl Serialize for SortedValues<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let result = self.0;
+        let mut map = serializer.serialize_map(Some(result.len()))?;
+        for key in result.destinations() {
+            if let Some(value) = result.get_single_value(key) {
+                map.serialize_entry(key, value)?;
+            } else if let Some(array) = result.get_array_values(key) {
+                map.serialize_entry(key, array)?;
+            } else if let Some(pairs) = result.get_map_values(key) {
+                map.serialize_entry(key, &OrderedPairs(pairs))?;
+            } else {
+                map.serialize_entry(key, &result.grouped_values[key])?;
+            }
+        }
+        map.end()
+    }
+}
+
+// Key-value arguments are serialized as objects, in the order the keys were given
+struct OrderedPairs<'a>(&'a [(String, ParseValue)]);
+
+impl Serialize for OrderedPairs<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+struct NamedResult<'a>(&'a String, &'a ParseResult);
+
+impl Serialize for NamedResult<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("name", self.0)?;
+        map.serialize_entry("values", &SortedValues(self.1))?;
+        map.serialize_entry(
+            "sub_parser",
+            &self
+                .1
+                .sub_parser_result
+                .as_ref()
+                .map(|(name, result)| NamedResult(name, result)),
+        )?;
+        map.end()
+    }
+}
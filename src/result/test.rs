@@ -0,0 +1,199 @@
+use crate::{
+    argument::{option::DuplicateKeys, DataType},
+    test::args,
+    Parser,
+};
+
+fn create_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.positional("path", DataType::Path(false));
+    parser
+        .option("-v", "verbose", DataType::Bool(false))
+        .defaults(&["false"]);
+    parser
+        .option("--ratio", "ratio", DataType::Float32(false))
+        .defaults(&["0.5"]);
+    parser.option("-n", "numbers", DataType::Int32(true));
+    parser
+        .option("--size", "size", DataType::Int32(false))
+        .arity(2);
+    parser
+        .option("-D", "defines", DataType::Int32(true))
+        .key_values(Some(DuplicateKeys::Reject));
+    let run_parser = parser.sub_parser("run");
+    run_parser.option("--name", "name", DataType::String(false));
+    run_parser.sub_parser("now");
+    parser
+}
+
+mod introspection {
+    use std::path::Path;
+
+    use crate::result::ParseValue;
+
+    use super::*;
+
+    #[test]
+    fn iteration() {
+        let parser = create_parser();
+        let result = parser
+            .parse_from(args(&["-n", "3", "a/b.txt", "-n", "-1", "run"]))
+            .unwrap();
+        assert_eq!(result.len(), 4);
+        assert!(!result.is_empty());
+        assert_eq!(
+            result.destinations().collect::<Vec<_>>(),
+            ["numbers", "path", "ratio", "verbose"]
+        );
+        assert_eq!(
+            result.single_values().collect::<Vec<_>>(),
+            [
+                ("path", &ParseValue::Path("a/b.txt".into())),
+                ("ratio", &ParseValue::Float32(0.5)),
+                ("verbose", &ParseValue::Bool(false)),
+            ]
+        );
+        assert_eq!(
+            result.array_values().collect::<Vec<_>>(),
+            [(
+                "numbers",
+                [ParseValue::Int32(3), ParseValue::Int32(-1)].as_slice()
+            )]
+        );
+        assert!(result.sub_parser_result().unwrap().is_empty());
+    }
+
+    #[test]
+    fn value_accessors() {
+        let parser = create_parser();
+        let result = parser.parse_from(args(&["a/b.txt", "-n", "7"])).unwrap();
+
+        let path = result.get_single_value("path").unwrap();
+        assert_eq!(path.data_type(), DataType::Path(false));
+        assert_eq!(path.as_path(), Some(Path::new("a/b.txt")));
+        assert_eq!(path.as_str(), None);
+        assert_eq!(path.to_string(), "a/b.txt");
+
+        let ratio = result.get_single_value("ratio").unwrap();
+        assert_eq!(ratio.as_f32(), Some(0.5));
+        assert_eq!(ratio.to_string(), "0.5");
+
+        let verbose = result.get_single_value("verbose").unwrap();
+        assert_eq!(verbose.as_bool(), Some(false));
+        assert_eq!(verbose.as_i32(), None);
+
+        let numbers = result.get_array_values("numbers").unwrap();
+        assert_eq!(numbers[0].as_i32(), Some(7));
+        assert_eq!(numbers[0].data_type(), DataType::Int32(false));
+        assert!(result.get_array_values("path").is_none());
+    }
+}
+
+mod source {
+    use crate::result::ValueSource;
+
+    use super::*;
+
+    #[test]
+    fn command_line_and_defaults() {
+        let parser = create_parser();
+        let result = parser
+            .parse_from(args(&["-n", "3", "a/b.txt", "-v", "-n", "-1"]))
+            .unwrap();
+        assert_eq!(
+            result.source("numbers"),
+            Some(ValueSource::CommandLine {
+                index: 4,
+                occurrence: 2
+            })
+        );
+        assert_eq!(
+            result.source("path"),
+            Some(ValueSource::CommandLine {
+                index: 2,
+                occurrence: 1
+            })
+        );
+        assert_eq!(
+            result.source("verbose"),
+            Some(ValueSource::CommandLine {
+                index: 3,
+                occurrence: 1
+            })
+        );
+        assert_eq!(result.source("ratio"), Some(ValueSource::Default));
+        assert_eq!(result.source("unknown"), None);
+    }
+
+    #[test]
+    fn sub_parser_and_global_options() {
+        let mut parser = create_parser();
+        parser
+            .option("-I", "include", DataType::Path(true))
+            .global(true);
+        let result = parser
+            .parse_from(args(&["-I", "a", "c", "run", "-I", "b", "--name", "x"]))
+            .unwrap();
+        assert_eq!(
+            result.source("include"),
+            Some(ValueSource::CommandLine {
+                index: 4,
+                occurrence: 2
+            })
+        );
+        let run_result = result.sub_parser_result().unwrap();
+        assert_eq!(run_result.source("include"), None);
+        assert_eq!(
+            run_result.source("name"),
+            Some(ValueSource::CommandLine {
+                index: 6,
+                occurrence: 1
+            })
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialize {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let parser = create_parser();
+        let result = parser
+            .parse_from(args(&[
+                "-n", "3", "a/b.txt", "--size", "4", "5", "-n", "-1", "run", "--name", "x y", "now",
+            ]))
+            .unwrap();
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            concat!(
+                r#"{"values":{"numbers":[3,-1],"path":"a/b.txt","ratio":0.5,"size":[[4,5]],"verbose":false},"#,
+                r#""sub_parser":{"name":"run","values":{"name":"x y"},"#,
+                r#""sub_parser":{"name":"now","values":{},"sub_parser":null}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn json_without_sub_parser() {
+        let parser = create_parser();
+        let result = parser.parse_from(args(&["-v", "c"])).unwrap();
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"values":{"path":"c","ratio":0.5,"verbose":true},"sub_parser":null}"#
+        );
+    }
+
+    #[test]
+    fn json_with_key_values() {
+        let parser = create_parser();
+        let result = parser
+            .parse_from(args(&["-D", "b=1", "c", "-D", "a=2"]))
+            .unwrap();
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"values":{"defines":{"b":1,"a":2},"path":"c","ratio":0.5,"verbose":false},"sub_parser":null}"#
+        );
+    }
+}
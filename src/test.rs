@@ -0,0 +1,1358 @@
+use crate::{
+    argument::{option::DuplicateKeys, DataType},
+    error::{DefinitionError, ParseError, ValueReason},
+    result::ValueSource,
+    Parser,
+};
+
+// Shared by the test modules of the whole crate
+pub(crate) fn args(raw_args: &[&str]) -> Vec<String> {
+    raw_args.iter().map(|raw_arg| raw_arg.to_string()).collect()
+}
+
+fn create_abbreviating_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.allow_abbreviations(true);
+    parser
+        .option("-v, --verbose", "verbose", DataType::Bool(false))
+        .defaults(&["false"]);
+    parser
+        .option("--verbatim", "verbatim", DataType::Bool(false))
+        .defaults(&["false"]);
+    parser.option("--out", "out", DataType::Path(false));
+    parser.option("--output-format", "output_format", DataType::String(false));
+    parser
+}
+
+fn create_sub_parser_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.allow_sub_parser_abbreviations(true);
+    parser.option("--root", "root", DataType::Path(false));
+    parser
+        .sub_parser("remove, rm")
+        .positional("target", DataType::String(false));
+    parser.sub_parser("list, ls");
+    parser.sub_parser("lint");
+    parser.sub_parser("debug").hidden(true);
+    parser
+}
+
+fn create_global_option_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser
+        .option("-v, --verbose", "verbose", DataType::Bool(false))
+        .defaults(&["false"])
+        .global(true);
+    parser
+        .option("-I, --include", "include", DataType::Path(true))
+        .global(true);
+    let run_parser = parser.sub_parser("run");
+    run_parser.option("--jobs", "jobs", DataType::Int32(false));
+    run_parser.sub_parser("test");
+    parser
+}
+
+fn create_arity_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.positional("files", DataType::String(true));
+    parser
+        .option("--size", "size", DataType::Int32(false))
+        .arity(2)
+        .defaults(&["800", "600"]);
+    parser
+        .option("--rename", "renames", DataType::String(true))
+        .arity(2);
+    parser
+        .option("--point", "points", DataType::Float32(true))
+        .arity_range(1, 3);
+    parser
+        .option("-f, --force", "force", DataType::Bool(false))
+        .defaults(&["false"]);
+    parser
+}
+
+fn create_implicit_value_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.positional("file", DataType::String(false));
+    parser
+        .option("--color", "color", DataType::String(false))
+        .implicit_value("always")
+        .defaults(&["auto"]);
+    parser.option("-n, --name", "name", DataType::String(false));
+    parser
+        .option("-f, --force", "force", DataType::Bool(false))
+        .defaults(&["false"]);
+    parser
+}
+
+fn create_deprecating_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.option("--color", "colors", DataType::String(true));
+    parser
+        .option("--colour", "colours", DataType::String(true))
+        .hidden(true)
+        .deprecated("it will be removed in 2.0", Some("--color"))
+        .forward_to_replacement(true);
+    parser
+        .option("--fast", "fast", DataType::Bool(false))
+        .defaults(&["false"])
+        .deprecated("it is always fast now", None);
+    parser
+        .sub_parser("remove, rm")
+        .positional("file", DataType::String(true))
+        .deprecated("use '--file' instead", None)
+        .is_required(false);
+    parser
+        .sub_parser("delete")
+        .hidden(true)
+        .deprecated("it was renamed", Some("remove"));
+    parser
+}
+
+fn create_copying_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser
+        .positional("mode", DataType::String(false))
+        .is_required(true);
+    parser
+        .positional("sources", DataType::Path(true))
+        .is_required(true);
+    parser
+        .positional("destination", DataType::Path(false))
+        .is_required(true);
+    parser
+        .option("-f, --force", "force", DataType::Bool(false))
+        .defaults(&["false"]);
+    parser
+}
+
+fn create_optional_tail_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser
+        .positional("source", DataType::Path(false))
+        .is_required(false);
+    parser
+        .positional("destination", DataType::Path(false))
+        .defaults(&["."]);
+    parser.positional("extra", DataType::String(true));
+    parser
+}
+
+fn create_version_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.program_name("tool").version("1.2.3");
+    parser
+        .positional("input", DataType::String(false))
+        .is_required(true);
+    parser
+        .sub_parser("run")
+        .positional("script", DataType::String(false))
+        .is_required(true);
+    parser
+}
+
+mod valid {
+    use std::{panic::catch_unwind, path::PathBuf};
+
+    use crate::result::{ParseResult, ParseValue};
+
+    use super::*;
+
+    fn groups<T>(
+        result: &ParseResult,
+        key: &str,
+        as_type: impl Fn(&ParseValue) -> Option<T>,
+    ) -> Vec<Vec<T>> {
+        result
+            .get_grouped_values(key)
+            .unwrap()
+            .iter()
+            .map(|group| group.iter().map(|value| as_type(value).unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn unique_abbreviation() {
+        let parser = create_abbreviating_parser();
+        let result = parser.parse_from(args(&["--verbo"])).unwrap();
+        assert_eq!(result.get_single::<bool>("verbose"), Some(true));
+        assert_eq!(result.get_single::<bool>("verbatim"), Some(false));
+    }
+
+    #[test]
+    fn abbreviation_in_sub_parser() {
+        let mut parser = create_abbreviating_parser();
+        parser.allow_sub_parser_abbreviations(true);
+        parser
+            .option("--quiet", "quiet", DataType::Bool(false))
+            .defaults(&["false"])
+            .global(true);
+        parser.sub_parser("run").sub_parser("tests");
+        let result = parser.parse_from(args(&["ru", "--qui", "te"])).unwrap();
+        assert_eq!(result.get_single::<bool>("quiet"), Some(true));
+        assert_eq!(result.sub_parser_name(), Some("run"));
+        let run_result = result.sub_parser_result().unwrap();
+        assert_eq!(run_result.sub_parser_name(), Some("tests"));
+    }
+
+    #[test]
+    fn exact_match_over_abbreviation() {
+        let parser = create_abbreviating_parser();
+        let result = parser.parse_from(args(&["--out", "a.txt"])).unwrap();
+        assert_eq!(result.get_single::<PathBuf>("out"), Some("a.txt".into()));
+        assert!(!result.has_single("output_format"));
+    }
+
+    #[test]
+    fn aliases() {
+        let mut parser = Parser::new();
+        parser.option("-o, --out, --output", "output", DataType::String(false));
+        parser.option("-t, --tag, --label", "tags", DataType::String(true));
+        let result = parser
+            .parse_from(args(&["--output", "a", "-t", "x", "--label", "y"]))
+            .unwrap();
+        assert_eq!(result.get_single::<String>("output"), Some("a".to_string()));
+        assert_eq!(
+            result.get_array::<String>("tags"),
+            Some(vec!["x".to_string(), "y".to_string()])
+        );
+    }
+
+    #[test]
+    fn abbreviation_of_aliases() {
+        let mut parser = Parser::new();
+        parser.allow_abbreviations(true);
+        parser.option("--out, --output", "output", DataType::String(false));
+        let result = parser.parse_from(args(&["--ou", "a"])).unwrap();
+        assert_eq!(result.get_single::<String>("output"), Some("a".to_string()));
+    }
+
+    #[test]
+    fn abbreviation_after_exact_match() {
+        let parser = create_abbreviating_parser();
+        let result = parser
+            .parse_from(args(&["--out", "a.txt", "--output-f", "json"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single::<String>("output_format"),
+            Some("json".to_string())
+        );
+    }
+
+    #[test]
+    fn sub_parser_name() {
+        let parser = create_sub_parser_parser();
+        let result = parser
+            .parse_from(args(&["--root", "/", "remove", "a"]))
+            .unwrap();
+        assert_eq!(result.get_single::<PathBuf>("root"), Some("/".into()));
+        assert_eq!(result.sub_parser_name(), Some("remove"));
+        let sub_result = result.sub_parser_result().unwrap();
+        assert_eq!(
+            sub_result.get_single::<String>("target"),
+            Some("a".to_string())
+        );
+    }
+
+    #[test]
+    fn sub_parser_alias() {
+        let parser = create_sub_parser_parser();
+        let result = parser.parse_from(args(&["rm", "a"])).unwrap();
+        assert_eq!(result.sub_parser_name(), Some("remove"));
+    }
+
+    #[test]
+    fn sub_parser_abbreviation() {
+        let parser = create_sub_parser_parser();
+        let result = parser.parse_from(args(&["rem", "a"])).unwrap();
+        assert_eq!(result.sub_parser_name(), Some("remove"));
+        let parser = create_sub_parser_parser();
+        let result = parser.parse_from(args(&["lis"])).unwrap();
+        assert_eq!(result.sub_parser_name(), Some("list"));
+    }
+
+    #[test]
+    fn hidden_sub_parser() {
+        let mut parser = create_sub_parser_parser();
+        let result = parser.clone().parse_from(args(&["debug"])).unwrap();
+        assert_eq!(result.sub_parser_name(), Some("debug"));
+
+        // Not an abbreviation of the hidden 'debug', so it's unambiguous
+        parser.sub_parser("deploy");
+        let result = parser.parse_from(args(&["de"])).unwrap();
+        assert_eq!(result.sub_parser_name(), Some("deploy"));
+    }
+
+    #[test]
+    fn no_sub_parser() {
+        let parser = create_sub_parser_parser();
+        let result = parser.parse_from(args(&[])).unwrap();
+        assert_eq!(result.sub_parser_name(), None);
+    }
+
+    #[test]
+    fn global_option_before_sub_parser() {
+        let parser = create_global_option_parser();
+        let result = parser.parse_from(args(&["-v", "run"])).unwrap();
+        assert_eq!(result.get_single::<bool>("verbose"), Some(true));
+        assert!(!result.sub_parser_result().unwrap().has_single("verbose"));
+    }
+
+    #[test]
+    fn global_option_after_sub_parser() {
+        let parser = create_global_option_parser();
+        let result = parser
+            .parse_from(args(&[
+                "-I", "a", "run", "--jobs", "2", "-I", "b", "test", "-v",
+            ]))
+            .unwrap();
+        assert_eq!(result.get_single::<bool>("verbose"), Some(true));
+        assert_eq!(
+            result.get_array::<PathBuf>("include"),
+            Some(vec!["a".into(), "b".into()])
+        );
+        let run_result = result.sub_parser_result().unwrap();
+        assert_eq!(run_result.get_single::<i32>("jobs"), Some(2));
+        assert!(!run_result.has_array("include"));
+    }
+
+    #[test]
+    fn global_option_default() {
+        let parser = create_global_option_parser();
+        let result = parser.parse_from(args(&["run", "test"])).unwrap();
+        assert_eq!(result.get_single::<bool>("verbose"), Some(false));
+    }
+
+    #[test]
+    fn valid_parser_tree() {
+        assert_eq!(create_global_option_parser().validate(), Ok(()));
+        assert_eq!(create_sub_parser_parser().validate(), Ok(()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let mut parser = Parser::new();
+        parser.positional("file", DataType::Path(false));
+        parser.option("-o", "output", DataType::Path(false));
+        let raw_args = [b"in\xff.txt".as_slice(), b"-o", b"out\xfe"]
+            .map(|raw_arg| OsStr::from_bytes(raw_arg).to_os_string());
+        let result = parser.parse_from_os(raw_args).unwrap();
+        let file = result.get_single::<PathBuf>("file").unwrap();
+        assert_eq!(file.as_os_str().as_bytes(), b"in\xff.txt");
+        let output = result.get_single::<PathBuf>("output").unwrap();
+        assert_eq!(output.as_os_str().as_bytes(), b"out\xfe");
+    }
+
+    #[test]
+    fn exact_arity() {
+        let parser = create_arity_parser();
+        let result = parser
+            .parse_from(args(&[
+                "--size", "1024", "-768", "--rename", "a", "b", "x", "--rename", "c", "d",
+            ]))
+            .unwrap();
+        assert_eq!(
+            groups(&result, "size", ParseValue::as_i32),
+            vec![vec![1024, -768]]
+        );
+        assert_eq!(
+            groups(&result, "renames", |value| value
+                .as_str()
+                .map(str::to_string)),
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["c".to_string(), "d".to_string()]
+            ]
+        );
+        assert_eq!(
+            result.get_array::<String>("files"),
+            Some(vec!["x".to_string()])
+        );
+    }
+
+    #[test]
+    fn arity_range() {
+        let parser = create_arity_parser();
+        let result = parser
+            .parse_from(args(&[
+                "--point", "1", "2", "-f", "--point", "3", "4", "5", "x",
+            ]))
+            .unwrap();
+        assert_eq!(
+            groups(&result, "points", ParseValue::as_f32),
+            vec![vec![1.0, 2.0], vec![3.0, 4.0, 5.0]]
+        );
+        assert_eq!(result.get_single::<bool>("force"), Some(true));
+        assert_eq!(
+            result.get_array::<String>("files"),
+            Some(vec!["x".to_string()])
+        );
+    }
+
+    #[test]
+    fn arity_defaults() {
+        let parser = create_arity_parser();
+        let result = parser.parse_from(args(&["x"])).unwrap();
+        assert_eq!(
+            groups(&result, "size", ParseValue::as_i32),
+            vec![vec![800, 600]]
+        );
+        assert!(!result.has_groups("renames"));
+    }
+
+    #[test]
+    fn implicit_value() {
+        let parser = create_implicit_value_parser();
+        let result = parser.clone().parse_from(args(&["a.txt"])).unwrap();
+        assert_eq!(
+            result.get_single_value("color").unwrap().as_str(),
+            Some("auto")
+        );
+        assert_eq!(result.source("color"), Some(ValueSource::Default));
+
+        // The value is never taken from the next argument
+        let result = parser
+            .clone()
+            .parse_from(args(&["--color", "a.txt"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single_value("color").unwrap().as_str(),
+            Some("always")
+        );
+        assert_eq!(
+            result.get_single_value("file").unwrap().as_str(),
+            Some("a.txt")
+        );
+
+        let result = parser
+            .parse_from(args(&["--color=never", "a.txt"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single_value("color").unwrap().as_str(),
+            Some("never")
+        );
+    }
+
+    #[test]
+    fn attached_value() {
+        let parser = create_implicit_value_parser();
+        let result = parser
+            .clone()
+            .parse_from(args(&["--name=a=b", "a.txt"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single_value("name").unwrap().as_str(),
+            Some("a=b")
+        );
+
+        let result = parser.parse_from(args(&["--name=", "a.txt"])).unwrap();
+        assert_eq!(result.get_single_value("name").unwrap().as_str(), Some(""));
+    }
+
+    #[test]
+    fn value_delimiter() {
+        let mut parser = Parser::new();
+        parser
+            .option("--tag", "tags", DataType::String(true))
+            .value_delimiter(',');
+        parser
+            .option("--size", "sizes", DataType::Int32(true))
+            .value_delimiter(':');
+        let result = parser
+            .parse_from(args(&["--tag", "a,b\\,c", "--tag=d", "--size", "1:2"]))
+            .unwrap();
+        let tags: Vec<_> = result
+            .get_array_values("tags")
+            .unwrap()
+            .iter()
+            .map(|value| value.as_str().unwrap())
+            .collect();
+        assert_eq!(tags, vec!["a", "b,c", "d"]);
+        assert_eq!(result.get_array_values("sizes").unwrap().len(), 2);
+        assert_eq!(
+            result.source("tags"),
+            Some(ValueSource::CommandLine {
+                index: 2,
+                occurrence: 2
+            })
+        );
+    }
+
+    #[test]
+    fn key_values() {
+        let mut parser = Parser::new();
+        parser
+            .option("-D, --define", "defines", DataType::String(true))
+            .key_values(Some(DuplicateKeys::LastWins));
+        parser
+            .option("--limit", "limits", DataType::Int32(true))
+            .key_values(Some(DuplicateKeys::Reject))
+            .value_delimiter(',')
+            .defaults(&["cpu=1"]);
+        let result = parser
+            .clone()
+            .parse_from(args(&["-D", "b=x=y", "-D", "a=", "--define=b=z"]))
+            .unwrap();
+        let defines: Vec<_> = result
+            .get_map_values("defines")
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str().unwrap()))
+            .collect();
+        assert_eq!(defines, vec![("b", "z"), ("a", "")]);
+        assert_eq!(
+            result.get_map_value("limits", "cpu").unwrap().as_i32(),
+            Some(1)
+        );
+        assert_eq!(result.source("limits"), Some(ValueSource::Default));
+
+        let result = parser
+            .parse_from(args(&["--limit", "mem=4,cpu=2"]))
+            .unwrap();
+        assert_eq!(
+            result.get_map_value("limits", "cpu").unwrap().as_i32(),
+            Some(2)
+        );
+        assert_eq!(result.get_map_values("limits").unwrap().len(), 2);
+        assert!(!result.has_map("defines"));
+    }
+
+    #[test]
+    fn deprecated_option() {
+        let parser = create_deprecating_parser();
+        let result = parser
+            .clone()
+            .parse_from(args(&[
+                "--colour", "red", "--color", "blue", "--colour", "green",
+            ]))
+            .unwrap();
+        let colors: Vec<_> = result
+            .get_array_values("colors")
+            .unwrap()
+            .iter()
+            .map(|value| value.as_str().unwrap())
+            .collect();
+        assert_eq!(colors, vec!["red", "blue", "green"]);
+        assert!(!result.has_array("colours"));
+        assert_eq!(
+            result.warnings(),
+            ["option '--colour' is deprecated, use '--color' instead: it will be removed in 2.0"]
+        );
+
+        let result = parser.parse_from(args(&["--color", "red"])).unwrap();
+        assert!(result.warnings().is_empty());
+    }
+
+    #[test]
+    fn deprecated_positional_and_sub_parser() {
+        let parser = create_deprecating_parser();
+        let result = parser.clone().parse_from(args(&["rm"])).unwrap();
+        assert!(result.warnings().is_empty());
+
+        let result = parser
+            .clone()
+            .parse_from(args(&["--fast", "rm", "a", "b"]))
+            .unwrap();
+        assert_eq!(
+            result.warnings(),
+            [
+                "option '--fast' is deprecated: it is always fast now",
+                "positional 'file' is deprecated: use '--file' instead"
+            ]
+        );
+
+        let result = parser.parse_from(args(&["delete"])).unwrap();
+        assert_eq!(result.sub_parser_name(), Some("delete"));
+        assert_eq!(
+            result.warnings(),
+            ["sub-command 'delete' is deprecated, use 'remove' instead: it was renamed"]
+        );
+    }
+
+    #[test]
+    fn version() {
+        let mut parser = create_version_parser();
+        parser.long_version("1.2.3 (built from abc123)");
+        let result = parser.clone().parse_from(args(&["-V"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::VersionRequested {
+                version: "tool 1.2.3".to_string()
+            }
+        );
+        let result = parser.clone().parse_from(args(&["--version"]));
+        assert_eq!(result.unwrap_err().to_string(), "tool 1.2.3");
+        let result = parser.clone().parse_from(args(&["-V", "--verbose"]));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "tool 1.2.3 (built from abc123)"
+        );
+        let result = parser.parse_from(args(&["--verbose", "--version"]));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "tool 1.2.3 (built from abc123)"
+        );
+    }
+
+    #[test]
+    fn version_abbreviation() {
+        let mut parser = create_version_parser();
+        parser.allow_abbreviations(true);
+        let result = parser.clone().parse_from(args(&["--vers"]));
+        assert_eq!(result.unwrap_err().to_string(), "tool 1.2.3");
+
+        parser
+            .option("--verbose", "verbose", DataType::Bool(false))
+            .defaults(&["false"]);
+        let result = parser.clone().parse_from(args(&["--ver"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::AmbiguousOption {
+                name: "--ver".to_string(),
+                candidates: vec!["--verbose".to_string(), "--version".to_string()],
+            }
+        );
+
+        parser.allow_abbreviations(false);
+        let result = parser.parse_from(args(&["--versoin"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::UnknownOption {
+                name: "--versoin".to_string(),
+                suggestion: Some("--version".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn propagated_version() {
+        let mut parser = create_version_parser();
+        parser.positionals.clear();
+        let result = parser.clone().parse_from(args(&["run", "-V"]));
+        assert!(matches!(result, Err(ParseError::UnknownOption { .. })));
+
+        parser.propagate_version(true);
+        assert_eq!(
+            parser.child_parsers["run"].version.as_deref(),
+            Some("1.2.3")
+        );
+        let result = parser.clone().parse_from(args(&["run", "-V"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::VersionRequested {
+                version: "tool run 1.2.3".to_string()
+            }
+        );
+
+        // Sub-parsers defined afterwards get the version too, unless they
+        // take its names for options of their own.
+        parser.sub_parser("walk");
+        assert_eq!(
+            parser.child_parsers["walk"].version.as_deref(),
+            Some("1.2.3")
+        );
+        parser
+            .sub_parser("stop")
+            .option("-V, --verbose", "verbose", DataType::Bool(false))
+            .defaults(&["false"]);
+        assert_eq!(parser.child_parsers["stop"].version, None);
+    }
+
+    #[test]
+    fn sub_parser_version() {
+        let mut parser = create_version_parser();
+        parser.positionals.clear();
+        parser.child_parsers.get_mut("run").unwrap().version("2.0");
+        let result = parser.parse_from(args(&["run", "-V"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::VersionRequested {
+                version: "tool run 2.0".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn moved_parser() {
+        fn create_parser() -> Parser {
+            let mut parser = Parser::new();
+            parser.positional("command", DataType::String(false));
+            parser
+                .sub_parser("run")
+                .positional("script", DataType::Path(false));
+            parser
+        }
+
+        let parser = Box::new(create_parser());
+        let cloned_parser = parser.clone();
+        let result = std::thread::spawn(move || cloned_parser.parse_from(args(&["x", "run", "a"])))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.sub_parser_name(), Some("run"));
+        assert!(parser.parse_from(args(&["x"])).is_ok());
+    }
+
+    #[test]
+    fn array_positional_before_required_positional() {
+        let parser = create_copying_parser();
+        let result = parser
+            .parse_from(args(&["copy", "a", "-f", "b", "c", "out"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single::<String>("mode"),
+            Some("copy".to_string())
+        );
+        assert_eq!(
+            result.get_array::<PathBuf>("sources"),
+            Some(vec![
+                PathBuf::from("a"),
+                PathBuf::from("b"),
+                PathBuf::from("c")
+            ])
+        );
+        assert_eq!(
+            result.get_single::<PathBuf>("destination"),
+            Some(PathBuf::from("out"))
+        );
+        assert_eq!(
+            result.source("destination"),
+            Some(ValueSource::CommandLine {
+                index: 5,
+                occurrence: 1
+            })
+        );
+
+        let mut parser = Parser::new();
+        parser.positional("sources", DataType::Path(true));
+        parser
+            .positional("destination", DataType::Path(false))
+            .is_required(true);
+        let result = parser.parse_from(args(&["out"])).unwrap();
+        assert!(!result.has_array("sources"));
+        assert_eq!(
+            result.get_single::<PathBuf>("destination"),
+            Some(PathBuf::from("out"))
+        );
+    }
+
+    #[test]
+    fn optional_positionals_filled_left_to_right() {
+        let result = create_optional_tail_parser().parse_from(args(&[])).unwrap();
+        assert_eq!(result.get_single::<PathBuf>("source"), None);
+        assert_eq!(
+            result.get_single::<PathBuf>("destination"),
+            Some(PathBuf::from("."))
+        );
+        assert_eq!(result.source("destination"), Some(ValueSource::Default));
+
+        let result = create_optional_tail_parser()
+            .parse_from(args(&["a"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single::<PathBuf>("source"),
+            Some(PathBuf::from("a"))
+        );
+        assert_eq!(
+            result.get_single::<PathBuf>("destination"),
+            Some(PathBuf::from("."))
+        );
+
+        let result = create_optional_tail_parser()
+            .parse_from(args(&["a", "b", "c", "d"]))
+            .unwrap();
+        assert_eq!(
+            result.get_single::<PathBuf>("destination"),
+            Some(PathBuf::from("b"))
+        );
+        assert_eq!(
+            result.get_array::<String>("extra"),
+            Some(vec!["c".to_string(), "d".to_string()])
+        );
+    }
+
+    #[test]
+    fn parser_is_send_sync_clone() {
+        fn assert_send_sync_clone<T: Send + Sync + Clone>() {}
+        assert_send_sync_clone::<Parser>();
+    }
+
+    #[test]
+    fn distinct_sub_parser_aliases() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("remove, rm, del");
+            parser.sub_parser("list, ls");
+            parser
+        });
+        assert!(result.is_ok());
+    }
+}
+
+mod invalid {
+    use std::{ffi::OsString, panic::catch_unwind};
+
+    use super::*;
+
+    #[test]
+    fn ambiguous_sub_parser_abbreviation() {
+        let parser = create_sub_parser_parser();
+        let result = parser.parse_from(args(&["li"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::AmbiguousSubParser {
+                name: "li".to_string(),
+                candidates: vec!["lint".to_string(), "list".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_sub_parser() {
+        let mut parser = create_sub_parser_parser();
+        parser.allow_sub_parser_abbreviations(false);
+        let result = parser.parse_from(args(&["rem", "a"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::UnknownSubParser {
+                name: "rem".to_string(),
+                suggestion: Some("rm".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn sub_parser_alias_conflict() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("remove, rm");
+            parser.sub_parser("rm");
+        });
+        assert!(result.is_err());
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("list");
+            parser.sub_parser("ls, list");
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn optional_positional_after_sub_parser() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("run");
+            let positional = parser.positional("target", DataType::String(false));
+            positional.is_required(false);
+        });
+        assert!(result.is_err());
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("run");
+            parser
+                .positional("target", DataType::String(false))
+                .defaults(&["a"]);
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn global_option_given_twice() {
+        let parser = create_global_option_parser();
+        let result = parser.parse_from(args(&["-v", "run", "-v"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::DuplicateValue {
+                destination: "verbose".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn global_option_conflict() {
+        // Sub-parsers added after the global option
+        let mut parser = create_global_option_parser();
+        let build_parser = parser.sub_parser("build");
+        assert_eq!(
+            build_parser
+                .try_option("-v", "very", DataType::Int32(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedName {
+                name: "-v".to_string()
+            }
+        );
+        assert_eq!(
+            build_parser
+                .try_positional("include", DataType::Path(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedDestination {
+                destination: "include".to_string()
+            }
+        );
+        // Including the sub-parsers of sub-parsers
+        assert_eq!(
+            build_parser
+                .sub_parser("docs")
+                .try_option("--verbose", "loud", DataType::Int32(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedName {
+                name: "--verbose".to_string()
+            }
+        );
+
+        // Sub-parsers added before the global option
+        let mut parser = Parser::new();
+        parser
+            .sub_parser("run")
+            .sub_parser("test")
+            .option("-q", "quiet", DataType::Int32(false));
+        assert_eq!(
+            parser
+                .option("-q, --quiet", "silent", DataType::Int32(false))
+                .try_global(true)
+                .unwrap_err(),
+            DefinitionError::OccupiedName {
+                name: "-q".to_string()
+            }
+        );
+        assert_eq!(
+            parser
+                .option("--level", "quiet", DataType::Int32(false))
+                .try_global(true)
+                .unwrap_err(),
+            DefinitionError::OccupiedDestination {
+                destination: "quiet".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn fallible_sub_parser_definitions() {
+        let mut parser = Parser::new();
+        parser.try_sub_parser("remove, rm").unwrap();
+        assert_eq!(
+            parser.try_sub_parser("rm").unwrap_err(),
+            DefinitionError::OccupiedName {
+                name: "rm".to_string()
+            }
+        );
+        assert_eq!(
+            parser.try_sub_parser("list, ls, list").unwrap_err(),
+            DefinitionError::RepeatedName {
+                name: "list".to_string()
+            }
+        );
+        assert_eq!(
+            parser
+                .try_positional("path", DataType::Path(true))
+                .unwrap_err(),
+            DefinitionError::ArrayPositionalWithSubParser {
+                destination: "path".to_string()
+            }
+        );
+
+        let mut parser = Parser::new();
+        parser.positional("path", DataType::Path(true));
+        assert_eq!(
+            parser.try_sub_parser("list").unwrap_err(),
+            DefinitionError::SubParserAfterArrayPositional {
+                destination: "path".to_string()
+            }
+        );
+
+        // Any optional positional makes sub-commands ambiguous, not just the last
+        let mut parser = Parser::new();
+        parser
+            .positional("source", DataType::Path(false))
+            .is_required(false);
+        parser.positional("destination", DataType::Path(false));
+        assert_eq!(
+            parser.try_sub_parser("list").unwrap_err(),
+            DefinitionError::SubParserAfterOptionalPositional {
+                destination: "source".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn missing_positional_after_array() {
+        // The positional after the array gets the last value first
+        let parser = create_copying_parser();
+        assert_eq!(
+            parser.parse_from(args(&["copy", "out"])).unwrap_err(),
+            ParseError::MissingPositional {
+                destination: "sources".to_string()
+            }
+        );
+
+        let mut parser = Parser::new();
+        parser.positional("sources", DataType::Path(true));
+        parser
+            .positional("destination", DataType::Path(false))
+            .is_required(true);
+        assert_eq!(
+            parser.parse_from(args(&[])).unwrap_err(),
+            ParseError::MissingPositional {
+                destination: "destination".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn validation_errors() {
+        let mut parser = Parser::new();
+        parser.option("--flag", "flag", DataType::Bool(false));
+        parser
+            .option("-v, --verbose", "verbose", DataType::Bool(false))
+            .defaults(&["false"])
+            .global(true);
+        let run_parser = parser.sub_parser("run");
+        run_parser.option("--dry-run", "dry_run", DataType::Bool(false));
+
+        assert_eq!(
+            parser.validate().unwrap_err(),
+            vec![
+                DefinitionError::BoolOptionWithoutDefault {
+                    destination: "flag".to_string()
+                },
+                DefinitionError::InSubParser {
+                    name: "run".to_string(),
+                    error: Box::new(DefinitionError::BoolOptionWithoutDefault {
+                        destination: "dry_run".to_string()
+                    })
+                },
+            ]
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn validation_before_parsing() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.option("--flag", "flag", DataType::Bool(false));
+            parser.parse_from(args(&[]))
+        });
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_string() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let mut parser = Parser::new();
+        parser.positional("name", DataType::String(false));
+        let raw_args = [OsStr::from_bytes(b"n\xffme").to_os_string()];
+        let result = parser.parse_from_os(raw_args);
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::InvalidValue {
+                destination: "name".to_string(),
+                value: "n\u{fffd}me".to_string(),
+                reason: ValueReason::InvalidUtf8,
+            }
+        );
+    }
+
+    #[test]
+    fn too_few_values() {
+        let parser = create_arity_parser();
+        let result = parser.parse_from(args(&["--rename", "a", "--force"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::TooFewValues {
+                option: "--rename".to_string(),
+                expected: 2,
+                found: 1,
+            }
+        );
+        let parser = create_arity_parser();
+        let result = parser.parse_from(args(&["--point"]));
+        assert!(matches!(result, Err(ParseError::TooFewValues { .. })));
+    }
+
+    #[test]
+    fn abbreviation_cuts_arity_short() {
+        let mut parser = create_arity_parser();
+        parser.allow_abbreviations(true);
+        let result = parser.parse_from(args(&["--size", "1", "--forc"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::TooFewValues {
+                option: "--size".to_string(),
+                expected: 2,
+                found: 1
+            }
+        );
+    }
+
+    #[test]
+    fn arity_given_twice() {
+        let parser = create_arity_parser();
+        let result = parser.parse_from(args(&["--size", "1", "2", "--size", "3", "4"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::DuplicateValue {
+                destination: "size".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_sub_parser_name() {
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("--list");
+        });
+        assert!(result.is_err());
+        let result = catch_unwind(|| {
+            let mut parser = Parser::new();
+            parser.sub_parser("list, ");
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ambiguous_abbreviation() {
+        let parser = create_abbreviating_parser();
+        let result = parser.parse_from(args(&["--verb"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::AmbiguousOption {
+                name: "--verb".to_string(),
+                candidates: vec!["--verbose".to_string(), "--verbatim".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn hidden_names_are_not_abbreviated() {
+        let mut parser = create_abbreviating_parser();
+        parser
+            .option("--verify", "verify", DataType::Bool(false))
+            .defaults(&["false"])
+            .hidden(true);
+        parser
+            .option("--vertical, --upright", "vertical", DataType::Bool(false))
+            .defaults(&["false"])
+            .hidden_names(&["--vertical"]);
+        let result = parser.clone().parse_from(args(&["--ver"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::AmbiguousOption {
+                name: "--ver".to_string(),
+                candidates: vec!["--verbose".to_string(), "--verbatim".to_string()],
+            }
+        );
+        let result = parser
+            .parse_from(args(&["--verify", "--vertical"]))
+            .unwrap();
+        assert_eq!(result.get_single::<bool>("verify"), Some(true));
+        assert_eq!(result.get_single::<bool>("vertical"), Some(true));
+    }
+
+    #[test]
+    fn abbreviation_not_allowed() {
+        let mut parser = create_abbreviating_parser();
+        parser.allow_abbreviations(false);
+        let result = parser.parse_from(args(&["--verbo"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::UnknownOption {
+                name: "--verbo".to_string(),
+                suggestion: Some("--verbose".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn short_names_not_abbreviated() {
+        let parser = create_abbreviating_parser();
+        let result = parser.parse_from(args(&["-"]));
+        assert!(matches!(result, Err(ParseError::UnknownOption { .. })));
+    }
+
+    #[test]
+    fn value_attached_to_flag() {
+        let parser = create_implicit_value_parser();
+        let result = parser.parse_from(args(&["--force=true", "a.txt"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::UnexpectedValue {
+                option: "--force".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn value_attached_to_arity_option() {
+        let parser = create_arity_parser();
+        let result = parser.parse_from(args(&["--size=800", "600"]));
+        assert!(matches!(result, Err(ParseError::UnexpectedValue { .. })));
+    }
+
+    #[test]
+    fn value_attached_to_short_name() {
+        let parser = create_implicit_value_parser();
+        let result = parser.parse_from(args(&["-n=x", "a.txt"]));
+        assert!(matches!(result, Err(ParseError::UnknownOption { .. })));
+    }
+
+    #[test]
+    fn invalid_delimited_value() {
+        let mut parser = Parser::new();
+        parser
+            .option("--size", "sizes", DataType::Int32(true))
+            .value_delimiter(',');
+        let result = parser.parse_from(args(&["--size", "1,x,3"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::InvalidValue {
+                destination: "--size".to_string(),
+                value: "x".to_string(),
+                reason: ValueReason::InvalidInt32("x".parse::<i32>().unwrap_err()),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_key_values() {
+        let mut parser = Parser::new();
+        parser
+            .option("-D", "defines", DataType::Int32(true))
+            .key_values(Some(DuplicateKeys::Reject));
+        let result = parser.clone().parse_from(args(&["-D", "a=1", "-D", "a=2"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::DuplicateKey {
+                option: "-D".to_string(),
+                key: "a".to_string()
+            }
+        );
+        let result = parser.clone().parse_from(args(&["-D", "a"]));
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+        let result = parser.parse_from(args(&["-D", "a=x"]));
+        assert!(matches!(result, Err(ParseError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn duplicate_key_across_sub_parsers() {
+        let mut parser = Parser::new();
+        parser
+            .option("-D", "defines", DataType::Int32(true))
+            .key_values(Some(DuplicateKeys::Reject))
+            .global(true);
+        parser.sub_parser("run");
+        let result = parser
+            .clone()
+            .parse_from(args(&["-D", "a=1", "run", "-D", "a=2"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::DuplicateKey {
+                option: "-D".to_string(),
+                key: "a".to_string()
+            }
+        );
+
+        let result = parser
+            .parse_from(args(&["-D", "a=1", "run", "-D", "b=2"]))
+            .unwrap();
+        assert_eq!(result.get_map_values("defines").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn invalid_replacement() {
+        let mut parser = create_deprecating_parser();
+        parser
+            .option("--old", "old", DataType::Int32(false))
+            .deprecated("it is old", Some("--color"))
+            .forward_to_replacement(true);
+        parser
+            .option("--older", "older", DataType::Int32(false))
+            .forward_to_replacement(true);
+        assert_eq!(
+            parser.validate().unwrap_err(),
+            vec![
+                DefinitionError::InvalidReplacement {
+                    destination: "old".to_string()
+                },
+                DefinitionError::InvalidReplacement {
+                    destination: "older".to_string()
+                },
+            ]
+        );
+
+        // Parsing without validation, like in release builds
+        let result = parser
+            .clone()
+            .parse_raw_args(&mut [(0, OsString::from("--old=1"))].into(), &[]);
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::InvalidReplacement {
+                option: "--old".to_string()
+            }
+        );
+        let result = parser.parse_raw_args(&mut [(0, OsString::from("--older=1"))].into(), &[]);
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::InvalidReplacement {
+                option: "--older".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_hidden_names() {
+        let mut parser = Parser::new();
+        let option = parser.option("-o, --output", "output", DataType::Path(false));
+        assert_eq!(
+            option.try_hidden_names(&["--out"]).unwrap_err(),
+            DefinitionError::UnknownHiddenName {
+                destination: "output".to_string(),
+                name: "--out".to_string()
+            }
+        );
+        assert_eq!(
+            option.try_hidden_names(&["-o", "--output"]).unwrap_err(),
+            DefinitionError::AllNamesHidden {
+                destination: "output".to_string()
+            }
+        );
+        // Failed calls leave the hidden names as they were
+        assert!(option.hidden_names.is_empty());
+        assert!(option.try_hidden_names(&["-o"]).is_ok());
+    }
+
+    #[test]
+    fn occupied_version_names() {
+        let mut parser = create_version_parser();
+        assert_eq!(
+            parser
+                .try_option("-V, --verbose", "verbose", DataType::Int32(false))
+                .unwrap_err(),
+            DefinitionError::OccupiedName {
+                name: "-V".to_string()
+            }
+        );
+
+        let mut parser = Parser::new();
+        parser.option("--version", "version", DataType::String(false));
+        assert!(parser.try_version("1.0").is_err());
+    }
+
+    #[test]
+    fn version_with_value() {
+        let parser = create_version_parser();
+        let result = parser.parse_from(args(&["--version=2"]));
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::UnexpectedValue {
+                option: "--version".to_string()
+            }
+        );
+    }
+}